@@ -0,0 +1,172 @@
+use crate::kmath::*;
+use serde::{Serialize, Deserialize};
+
+/*
+
+Tile edges are told apart by colour alone, which doesn't work for everyone. Two
+things help, both applied in Renderer's draw_tile functions so the board, the
+palette, the ghost and thumbnails all get them:
+
+Alternative palettes swap the edge colours the levels use for ones that stay
+apart with each kind of colour blindness, based on the Okabe-Ito set.
+
+Edge symbols put a small shape on each edge, one per colour, so edges can be
+matched by shape with no colour at all. Shapes are given in edge space: u runs
+along the edge 0..1 and v goes from the edge (0) to the centre of the tile
+(0.5), so a symbol turns with its tile.
+
+Only the colours the editor knows (see editor.rs) get remapped or a symbol,
+anything else draws as it is.
+
+*/
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColourVision {
+    Normal,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+// the editor's colours: red, green, blue, black, white, magenta, yellow, cyan
+const RED: Vec3 = Vec3 {x: 1.0, y: 0.0, z: 0.0};
+const GREEN: Vec3 = Vec3 {x: 0.0, y: 1.0, z: 0.0};
+const BLUE: Vec3 = Vec3 {x: 0.0, y: 0.0, z: 1.0};
+const WHITE: Vec3 = Vec3 {x: 1.0, y: 1.0, z: 1.0};
+const MAGENTA: Vec3 = Vec3 {x: 1.0, y: 0.0, z: 1.0};
+const YELLOW: Vec3 = Vec3 {x: 1.0, y: 1.0, z: 0.0};
+const CYAN: Vec3 = Vec3 {x: 0.0, y: 1.0, z: 1.0};
+
+fn same_colour(a: Vec3, b: Vec3) -> bool {
+    (a.x - b.x).abs() < 0.01 && (a.y - b.y).abs() < 0.01 && (a.z - b.z).abs() < 0.01
+}
+
+impl ColourVision {
+    pub fn next(self) -> ColourVision {
+        match self {
+            ColourVision::Normal => ColourVision::Deuteranopia,
+            ColourVision::Deuteranopia => ColourVision::Protanopia,
+            ColourVision::Protanopia => ColourVision::Tritanopia,
+            ColourVision::Tritanopia => ColourVision::Normal,
+        }
+    }
+
+    // (level colour, what to draw instead)
+    fn palette(self) -> &'static [(Vec3, Vec3)] {
+        match self {
+            ColourVision::Normal => &[],
+            // red and green are the problem, so spread things along blue to orange
+            ColourVision::Deuteranopia => &[
+                (RED, Vec3 {x: 0.9, y: 0.6, z: 0.0}),
+                (GREEN, Vec3 {x: 0.0, y: 0.45, z: 0.7}),
+                (YELLOW, Vec3 {x: 0.95, y: 0.9, z: 0.25}),
+                (BLUE, Vec3 {x: 0.8, y: 0.6, z: 0.7}),
+                (MAGENTA, Vec3 {x: 0.35, y: 0.7, z: 0.9}),
+                (CYAN, Vec3 {x: 0.0, y: 0.6, z: 0.5}),
+            ],
+            // same again but reds look dark, so red goes to the dark blue
+            ColourVision::Protanopia => &[
+                (RED, Vec3 {x: 0.0, y: 0.45, z: 0.7}),
+                (GREEN, Vec3 {x: 0.9, y: 0.6, z: 0.0}),
+                (YELLOW, Vec3 {x: 0.95, y: 0.9, z: 0.25}),
+                (BLUE, Vec3 {x: 0.35, y: 0.7, z: 0.9}),
+                (MAGENTA, Vec3 {x: 0.8, y: 0.6, z: 0.7}),
+                (CYAN, Vec3 {x: 0.0, y: 0.6, z: 0.5}),
+            ],
+            // blue and yellow are the problem, red to cyan still works
+            ColourVision::Tritanopia => &[
+                (RED, Vec3 {x: 0.85, y: 0.1, z: 0.1}),
+                (GREEN, Vec3 {x: 0.0, y: 0.65, z: 0.6}),
+                (YELLOW, Vec3 {x: 1.0, y: 0.6, z: 0.75}),
+                (BLUE, Vec3 {x: 0.1, y: 0.3, z: 0.35}),
+                (MAGENTA, Vec3 {x: 0.6, y: 0.0, z: 0.3}),
+                (CYAN, Vec3 {x: 0.6, y: 0.9, z: 0.9}),
+            ],
+        }
+    }
+
+    pub fn remap(self, c: Vec3) -> Vec3 {
+        self.palette().iter()
+            .find(|(from, _)| same_colour(*from, c))
+            .map(|(_, to)| *to)
+            .unwrap_or(c)
+    }
+}
+
+// convex polygons in edge space for an edge of this (level, not remapped) colour.
+// black is the usual background edge so it's left plain
+pub fn edge_symbol(c: Vec3) -> Vec<Vec<Vec2>> {
+    let quad = |u0: f32, v0: f32, u1: f32, v1: f32| vec![Vec2::new(u0, v0), Vec2::new(u1, v0), Vec2::new(u1, v1), Vec2::new(u0, v1)];
+    if same_colour(c, RED) {
+        // dot
+        vec![quad(0.44, 0.14, 0.56, 0.26)]
+    } else if same_colour(c, GREEN) {
+        // stripe along the edge
+        vec![quad(0.3, 0.17, 0.7, 0.23)]
+    } else if same_colour(c, BLUE) {
+        // two dots
+        vec![quad(0.34, 0.15, 0.44, 0.25), quad(0.56, 0.15, 0.66, 0.25)]
+    } else if same_colour(c, YELLOW) {
+        // chevron pointing in
+        vec![vec![Vec2::new(0.36, 0.12), Vec2::new(0.64, 0.12), Vec2::new(0.5, 0.3)]]
+    } else if same_colour(c, MAGENTA) {
+        // plus
+        vec![quad(0.36, 0.17, 0.64, 0.23), quad(0.47, 0.08, 0.53, 0.32)]
+    } else if same_colour(c, CYAN) {
+        // two stripes
+        vec![quad(0.32, 0.11, 0.68, 0.16), quad(0.32, 0.22, 0.68, 0.27)]
+    } else if same_colour(c, WHITE) {
+        // ring
+        vec![quad(0.38, 0.1, 0.62, 0.14), quad(0.38, 0.26, 0.62, 0.3), quad(0.38, 0.1, 0.42, 0.3), quad(0.58, 0.1, 0.62, 0.3)]
+    } else {
+        Vec::new()
+    }
+}
+
+// edge space to tile space (0..1 both ways, y down) for edge i of N, E, S, W,
+// going clockwise round the tile
+pub fn edge_to_tile(edge: usize, p: Vec2) -> Vec2 {
+    match edge {
+        0 => Vec2::new(p.x, p.y),
+        1 => Vec2::new(1.0 - p.y, p.x),
+        2 => Vec2::new(1.0 - p.x, 1.0 - p.y),
+        _ => Vec2::new(p.y, 1.0 - p.x),
+    }
+}
+
+// dark symbols on light colours and the other way round
+pub fn symbol_colour(edge_colour: Vec3, alpha: f32) -> Vec4 {
+    let luminance = 0.3 * edge_colour.x + 0.59 * edge_colour.y + 0.11 * edge_colour.z;
+    if luminance > 0.5 {
+        Vec4::new(0.0, 0.0, 0.0, 0.8 * alpha)
+    } else {
+        Vec4::new(1.0, 1.0, 1.0, 0.8 * alpha)
+    }
+}
+
+#[test]
+fn test_accessibility() {
+    assert_eq!(ColourVision::Normal.remap(RED), RED);
+    let (r, g) = (ColourVision::Deuteranopia.remap(RED), ColourVision::Deuteranopia.remap(GREEN));
+    assert!(!same_colour(r, RED) && !same_colour(r, g));
+    // colours that aren't the editor's are left alone
+    let odd = Vec3::new(0.3, 0.2, 0.1);
+    assert_eq!(ColourVision::Tritanopia.remap(odd), odd);
+
+    // every colour but black gets its own symbol
+    let colours = [RED, GREEN, BLUE, WHITE, MAGENTA, YELLOW, CYAN];
+    for (i, a) in colours.iter().enumerate() {
+        assert!(!edge_symbol(*a).is_empty());
+        for b in colours.iter().skip(i + 1) {
+            assert_ne!(edge_symbol(*a), edge_symbol(*b));
+        }
+    }
+    assert!(edge_symbol(Vec3::new(0.0, 0.0, 0.0)).is_empty());
+
+    // edges go round clockwise, the middle of each lands on its side
+    let mid = Vec2::new(0.5, 0.0);
+    assert_eq!(edge_to_tile(0, mid), Vec2::new(0.5, 0.0));
+    assert_eq!(edge_to_tile(1, mid), Vec2::new(1.0, 0.5));
+    assert_eq!(edge_to_tile(2, mid), Vec2::new(0.5, 1.0));
+    assert_eq!(edge_to_tile(3, mid), Vec2::new(0.0, 0.5));
+}
@@ -0,0 +1,193 @@
+use crate::level::*;
+
+/*
+
+Little tweens for game feel, all advanced by the frame dt from game.rs.
+Nothing here affects game state, draw_level just reads it to pose things.
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tween {
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl Tween {
+    pub fn new(duration: f32) -> Tween {
+        Tween {elapsed: 0.0, duration}
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    pub fn t(&self) -> f32 {
+        if self.duration <= 0.0 { 1.0 } else { self.elapsed / self.duration }
+    }
+
+    pub fn done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn finish(&mut self) {
+        self.elapsed = self.duration;
+    }
+}
+
+pub fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+// overshoots a bit then settles, for pop in
+pub fn ease_out_back(t: f32) -> f32 {
+    let c = 1.70158;
+    1.0 + (c + 1.0) * (t - 1.0).powi(3) + c * (t - 1.0).powi(2)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellAnimKind {
+    Place,
+    // the tile that was there, shrinking away
+    Remove(Tile),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellAnim {
+    pub kind: CellAnimKind,
+    pub tween: Tween,
+}
+
+const PLACE_TIME: f32 = 0.15;
+const REMOVE_TIME: f32 = 0.12;
+const SHAKE_TIME: f32 = 0.3;
+const SWEEP_TIME: f32 = 1.0;
+const ROTATE_SPEED: f32 = 20.0;
+
+pub struct Animations {
+    cells: Vec<(usize, CellAnim)>,
+    // ghost is drawn turned back by this much, easing to zero
+    ghost_angle: f32,
+    shake: Option<(usize, Tween)>,
+    sweep: Option<Tween>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Animations {
+    pub fn new() -> Animations {
+        Animations {
+            cells: Vec::new(),
+            ghost_angle: 0.0,
+            shake: None,
+            sweep: None,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for (_, anim) in self.cells.iter_mut() {
+            anim.tween.update(dt);
+        }
+        self.cells.retain(|(_, anim)| !anim.tween.done());
+
+        self.ghost_angle -= self.ghost_angle * (dt * ROTATE_SPEED).min(1.0);
+        if self.ghost_angle.abs() < 0.001 {
+            self.ghost_angle = 0.0;
+        }
+
+        if let Some((_, tween)) = &mut self.shake {
+            tween.update(dt);
+            if tween.done() {
+                self.shake = None;
+            }
+        }
+        if let Some(tween) = &mut self.sweep {
+            tween.update(dt);
+            if tween.done() {
+                self.sweep = None;
+            }
+        }
+    }
+
+    // eg on level change, so nothing carries over
+    pub fn clear(&mut self) {
+        *self = Animations::new();
+    }
+
+    pub fn tile_changed(&mut self, idx: usize, old: Option<Tile>, new: Option<Tile>) {
+        if old == new {
+            return;
+        }
+        self.cells.retain(|(i, _)| *i != idx);
+        let anim = match (old, new) {
+            (_, Some(_)) => CellAnim {kind: CellAnimKind::Place, tween: Tween::new(PLACE_TIME)},
+            (Some(old), None) => CellAnim {kind: CellAnimKind::Remove(old), tween: Tween::new(REMOVE_TIME)},
+            (None, None) => return,
+        };
+        self.cells.push((idx, anim));
+    }
+
+    pub fn rotated(&mut self, angle: f32) {
+        self.ghost_angle = (self.ghost_angle + angle).clamp(-std::f32::consts::PI, std::f32::consts::PI);
+    }
+
+    pub fn rejected(&mut self, idx: usize) {
+        self.shake = Some((idx, Tween::new(SHAKE_TIME)));
+    }
+
+    pub fn level_complete(&mut self) {
+        self.sweep = Some(Tween::new(SWEEP_TIME));
+    }
+
+    pub fn cell(&self, idx: usize) -> Option<CellAnim> {
+        self.cells.iter().find(|(i, _)| *i == idx).map(|(_, anim)| *anim)
+    }
+
+    pub fn ghost_angle(&self) -> f32 {
+        self.ghost_angle
+    }
+
+    // horizontal wobble as a fraction of the tile width
+    pub fn shake_offset(&self, idx: usize) -> f32 {
+        match self.shake {
+            Some((i, tween)) if i == idx => {
+                let t = tween.t();
+                (t * std::f32::consts::PI * 6.0).sin() * 0.1 * (1.0 - t)
+            },
+            _ => 0.0,
+        }
+    }
+
+    // where the level complete highlight is, 0..1 along the board diagonal
+    pub fn sweep(&self) -> Option<f32> {
+        self.sweep.map(|tween| ease_out_cubic(tween.t()) * 1.4 - 0.2)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.cells.is_empty() && self.ghost_angle == 0.0 && self.shake.is_none() && self.sweep.is_none()
+    }
+}
+
+#[test]
+fn test_animations_settle() {
+    let mut anims = Animations::new();
+    anims.tile_changed(3, None, Some([crate::kmath::Vec3::new(1.0, 0.0, 0.0); 4]));
+    anims.rotated(-std::f32::consts::FRAC_PI_2);
+    anims.rejected(2);
+    anims.level_complete();
+
+    assert!(anims.cell(3).is_some());
+    assert!(anims.ghost_angle() < 0.0);
+    anims.update(0.05);
+    assert!(anims.shake_offset(2) != 0.0);
+    assert_eq!(anims.shake_offset(1), 0.0);
+
+    for _ in 0..120 {
+        anims.update(1.0 / 60.0);
+    }
+    assert!(anims.is_idle());
+}
@@ -2,26 +2,256 @@ use crate::level::*;
 use crate::renderer::*;
 use crate::kmath::*;
 use crate::rect::*;
+use crate::raster::*;
+use crate::anim::*;
+use crate::audio::*;
+use crate::gui::*;
+use crate::progress::*;
+use crate::camera::*;
+use crate::settings::*;
 use std::fs;
 use std::io::prelude::*;
 use std::collections::HashMap;
-use glutin::event::VirtualKeyCode;
 
-pub const LEVEL_PATH: &'static str = "./levels/";
+pub const LEVEL_PATH: &str = "./levels/";
+pub const SCREENSHOT_PATH: &str = "./screenshots/";
+
+/*
+
+Everything a frontend can ask the game to do. Frontends map their own input
+(glutin keys, terminal keys, replays, tests) onto these and call Application::apply.
+Primary and Secondary act at the last position given to cursor_moved.
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Primary,
+    Secondary,
+    SelectTile(usize),
+    NextTile,
+    PrevTile,
+    RotateCw,
+    RotateCcw,
+    Place(usize),
+    Remove(usize),
+    NextLevel,
+    PrevLevel,
+    ToggleEdit,
+    TogglePaint,
+    ToggleAutoRotate,
+    ToggleHints,
+    CycleSymmetry,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    Save,
+    Undo,
+    Export,
+    LevelSelect,
+    // held to drag the board around
+    Pan,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    ScrollPaletteUp,
+    ScrollPaletteDown,
+    Settings,
+    CycleWindowMode,
+    CycleResolution,
+    ToggleVsync,
+    CycleFrameCap,
+    CycleTheme,
+    CycleColourVision,
+    ToggleEdgeSymbols,
+    CycleTileStyle,
+    ToggleFog,
+    ToggleVignette,
+    CyclePixelSize,
+    Quit,
+}
+
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    // names match the variants, with an index for the ones that take one: "SelectTile(2)"
+    fn from_str(s: &str) -> Result<Action, String> {
+        let (name, arg) = match s.find('(') {
+            Some(open) if s.ends_with(')') => {
+                let arg = s[open + 1..s.len() - 1].trim().parse::<usize>()
+                    .map_err(|_| format!("invalid index in action {:?}", s))?;
+                (&s[..open], Some(arg))
+            },
+            _ => (s, None),
+        };
+
+        match (name, arg) {
+            ("Primary", None) => Ok(Action::Primary),
+            ("Secondary", None) => Ok(Action::Secondary),
+            ("SelectTile", Some(i)) => Ok(Action::SelectTile(i)),
+            ("NextTile", None) => Ok(Action::NextTile),
+            ("PrevTile", None) => Ok(Action::PrevTile),
+            ("RotateCw", None) => Ok(Action::RotateCw),
+            ("RotateCcw", None) => Ok(Action::RotateCcw),
+            ("Place", Some(i)) => Ok(Action::Place(i)),
+            ("Remove", Some(i)) => Ok(Action::Remove(i)),
+            ("NextLevel", None) => Ok(Action::NextLevel),
+            ("PrevLevel", None) => Ok(Action::PrevLevel),
+            ("ToggleEdit", None) => Ok(Action::ToggleEdit),
+            ("TogglePaint", None) => Ok(Action::TogglePaint),
+            ("ToggleAutoRotate", None) => Ok(Action::ToggleAutoRotate),
+            ("ToggleHints", None) => Ok(Action::ToggleHints),
+            ("CycleSymmetry", None) => Ok(Action::CycleSymmetry),
+            ("VolumeUp", None) => Ok(Action::VolumeUp),
+            ("VolumeDown", None) => Ok(Action::VolumeDown),
+            ("ToggleMute", None) => Ok(Action::ToggleMute),
+            ("Save", None) => Ok(Action::Save),
+            ("Undo", None) => Ok(Action::Undo),
+            ("Export", None) => Ok(Action::Export),
+            ("LevelSelect", None) => Ok(Action::LevelSelect),
+            ("Pan", None) => Ok(Action::Pan),
+            ("PanLeft", None) => Ok(Action::PanLeft),
+            ("PanRight", None) => Ok(Action::PanRight),
+            ("PanUp", None) => Ok(Action::PanUp),
+            ("PanDown", None) => Ok(Action::PanDown),
+            ("ZoomIn", None) => Ok(Action::ZoomIn),
+            ("ZoomOut", None) => Ok(Action::ZoomOut),
+            ("ScrollPaletteUp", None) => Ok(Action::ScrollPaletteUp),
+            ("ScrollPaletteDown", None) => Ok(Action::ScrollPaletteDown),
+            ("Settings", None) => Ok(Action::Settings),
+            ("CycleWindowMode", None) => Ok(Action::CycleWindowMode),
+            ("CycleResolution", None) => Ok(Action::CycleResolution),
+            ("ToggleVsync", None) => Ok(Action::ToggleVsync),
+            ("CycleFrameCap", None) => Ok(Action::CycleFrameCap),
+            ("CycleTheme", None) => Ok(Action::CycleTheme),
+            ("CycleColourVision", None) => Ok(Action::CycleColourVision),
+            ("ToggleEdgeSymbols", None) => Ok(Action::ToggleEdgeSymbols),
+            ("CycleTileStyle", None) => Ok(Action::CycleTileStyle),
+            ("ToggleFog", None) => Ok(Action::ToggleFog),
+            ("ToggleVignette", None) => Ok(Action::ToggleVignette),
+            ("CyclePixelSize", None) => Ok(Action::CyclePixelSize),
+            ("Quit", None) => Ok(Action::Quit),
+            ("SelectTile", None) | ("Place", None) | ("Remove", None) => Err(format!("action {:?} needs an index, eg {}(0)", s, name)),
+            (_, Some(_)) if name.parse::<Action>().is_ok() => Err(format!("action {:?} does not take an index", name)),
+            _ => Err(format!("unknown action {:?}", s)),
+        }
+    }
+}
+
+// how far the cursor moves with the button held before a press becomes a drag
+const DRAG_THRESHOLD: f32 = 0.01;
+const RETURN_TIME: f32 = 0.2;
+const MESSAGE_TIME: f32 = 2.5;
+// arrow keys move the view by this much of the board rect
+const PAN_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DragSource {
+    Palette(usize),
+    Board(usize),
+}
+
+struct Drag {
+    source: DragSource,
+    tile: Tile,
+    start: Vec2,
+    // board drags only take the tile off the board once the cursor moves
+    lifted: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StrokeKind {
+    Paint,
+    Erase,
+}
+
+// holding a button in paint mode, one undo step for the whole stroke
+struct Stroke {
+    kind: StrokeKind,
+    last_pos: Vec2,
+    changed: bool,
+}
+
+// an invalid drop sliding back to where it came from
+struct ReturnAnim {
+    tile: Tile,
+    from: Rect,
+    to: Rect,
+    t: f32,
+    restore: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Game,
+    LevelSelect,
+    Settings,
+}
 
 pub struct Application {
     level_datas: Vec<LevelData>,
     levels: Vec<Level>,
     current_level: usize,
 
+    screen: Screen,
+    progress: Progress,
+    // where progress is saved, None for tests
+    progress_path: Option<String>,
+    settings: Settings,
+    settings_path: Option<String>,
+
     editing: bool,
     // bool editor etc
     frame_gui: Vec<(GUIElement, Rect)>,
+    cursor_pos: Vec2,
+    drag: Option<Drag>,
+    returning: Vec<ReturnAnim>,
+    painting: bool,
+    stroke: Option<Stroke>,
+    auto_rotate: bool,
+    show_hints: bool,
+    anims: Animations,
+    audio: Audio,
+    message: Option<(String, Tween)>,
+    palette: Option<Palette>,
+    palette_scroll: usize,
+    // scroll the palette to the selected tile next frame
+    reveal_selection: bool,
+    gui: Gui,
+    camera: Camera,
+    // last cursor position while dragging the view
+    panning: Option<Vec2>,
+    // what the layout was last built for: screen, level, aspect ratio bits, palette scroll, selected tile
+    gui_key: Option<(Screen, usize, u32, usize, i32)>,
+}
+
+impl Default for Application {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Application {
     pub fn new() -> Application {
-        let level_datas = load_level_data();
+        let mut application = Application::with_levels(load_level_data());
+        application.progress = Progress::load(PROGRESS_PATH);
+        application.progress_path = Some(PROGRESS_PATH.to_owned());
+        application.settings = Settings::load(SETTINGS_PATH);
+        application.settings_path = Some(SETTINGS_PATH.to_owned());
+        application.audio.volume = application.settings.volume;
+        application.audio.muted = application.settings.muted;
+        for (ld, l) in application.level_datas.iter().zip(application.levels.iter_mut()) {
+            if let Some(solution) = application.progress.solution(ld) {
+                l.current_solution = solution;
+            }
+        }
+        application
+    }
+
+    pub fn with_levels(level_datas: Vec<LevelData>) -> Application {
         let mut levels =  Vec::new();
 
         for ld in level_datas.iter() {
@@ -30,6 +260,9 @@ impl Application {
                 current_solution: ld.fresh_solution(),
                 selected_tile: ld.tile_choices[0],
                 selected_tile_idx: 0,
+                history: Vec::new(),
+                symmetry: ld.symmetry,
+                unsaved: false,
             });
         }
 
@@ -37,107 +270,1055 @@ impl Application {
             level_datas,
             levels,
             current_level: 0,
+            screen: Screen::Game,
+            progress: Progress::default(),
+            progress_path: None,
+            settings: Settings::default(),
+            settings_path: None,
             editing: false,
             frame_gui: Vec::new(),
+            cursor_pos: Vec2::new(-1.0, -1.0),
+            drag: None,
+            returning: Vec::new(),
+            painting: false,
+            stroke: None,
+            auto_rotate: false,
+            show_hints: false,
+            anims: Animations::new(),
+            audio: Audio::new(),
+            message: None,
+            palette: None,
+            palette_scroll: 0,
+            reveal_selection: true,
+            gui: Gui::new(),
+            camera: Camera::new(),
+            panning: None,
+            gui_key: None,
+        }
+    }
+
+    pub fn draw(&mut self, renderer: &mut Renderer, aspect_ratio: f32) {
+        renderer.colour_vision = self.settings.colour_vision;
+        renderer.edge_symbols = self.settings.edge_symbols;
+        renderer.tile_style = self.settings.tile_style;
+        match self.screen {
+            Screen::Game => self.draw_game(renderer, aspect_ratio),
+            Screen::LevelSelect => self.draw_level_select(renderer, aspect_ratio),
+            Screen::Settings => self.draw_settings(renderer, aspect_ratio),
         }
+        self.draw_widgets(renderer, aspect_ratio);
+        self.draw_tooltip(renderer, aspect_ratio);
     }
 
-    pub fn draw(&mut self, renderer: &mut Renderer, aspect_ratio: f32, cursor_pos: Vec2) {
+    fn set_gui(&mut self, key: (Screen, usize, u32, usize, i32), gui: Vec<(GUIElement, Rect)>) {
+        self.gui.set_widgets(gui.iter().map(|(e, r)| Widget::new(*e, *r).with_tooltip(tooltip(*e))).collect());
+        self.frame_gui = gui;
+        self.gui_key = Some(key);
+    }
+
+    fn draw_game(&mut self, renderer: &mut Renderer, aspect_ratio: f32) {
+        let palette = Palette::new(&self.level_datas[self.current_level].tile_choices, aspect_ratio);
+        if self.reveal_selection {
+            let selected = self.levels[self.current_level].selected_tile_idx as usize;
+            self.palette_scroll = palette.reveal(self.palette_scroll, selected);
+            self.reveal_selection = false;
+        }
+        self.palette_scroll = self.palette_scroll.min(palette.max_scroll());
+
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        let key = (Screen::Game, self.current_level, aspect_ratio.to_bits(), self.palette_scroll, l.selected_tile_idx);
+        if self.gui_key != Some(key) {
+            let gui = calculate_gui(&palette, self.palette_scroll, ld.w, ld.h, aspect_ratio, l.selected_tile_idx);
+            self.set_gui(key, gui);
+        }
         let l = &self.levels[self.current_level];
         let ld = &self.level_datas[self.current_level];
-        self.frame_gui = calculate_gui(ld.tile_choices.len(), ld.w, ld.h, aspect_ratio, l.selected_tile_idx);
 
+        let ghost = match &self.drag {
+            Some(drag) if drag.lifted => drag.tile,
+            _ => l.selected_tile,
+        };
         let fixed: Vec<bool> = ld.fixed_tiles.iter().map(|x| x.is_some()).collect();
-        draw_level(renderer, cursor_pos, l.selected_tile, &ld.tile_choices, &l.current_solution, &fixed, &self.frame_gui);
+        let legal = if self.show_hints {
+            Some(legal_moves(ld.w, ld.h, &l.current_solution, l.selected_tile, &ld.tile_choices))
+        } else {
+            None
+        };
+        // the board goes through the camera, everything else is drawn as laid out
+        let (board_gui, rest_gui): (Vec<_>, Vec<_>) = self.frame_gui.iter()
+            .filter(|(e, _)| *e != GUIElement::Background)
+            .partition(|(e, _)| matches!(e, GUIElement::GameBoard | GUIElement::GameTile(_)));
+        let colours = self.settings.theme.colours();
+        if let Some(r) = self.gui_rect(GUIElement::Background) {
+            renderer.draw_rect(r, colours.background, 1.0);
+        }
+        if let Some(board) = self.gui_rect(GUIElement::GameBoard) {
+            let world_cursor = if board.contains(self.cursor_pos) { self.camera.to_world(board, self.cursor_pos) } else { Vec2::new(-1.0, -1.0) };
+            self.camera.apply(renderer, board);
+            draw_level(renderer, world_cursor, ghost, &ld.tile_choices, &l.current_solution, &fixed, legal.as_deref(), Some(&self.anims), &board_gui);
+            renderer.reset_view();
+        }
+        draw_level(renderer, self.cursor_pos, ghost, &ld.tile_choices, &l.current_solution, &fixed, legal.as_deref(), Some(&self.anims), &rest_gui);
+
+        let tile_size = self.tile_screen_rect(0)
+            .map(|r| Vec2::new(r.w, r.h))
+            .unwrap_or(Vec2::new(0.1, 0.1));
+
+        if let Some(drag) = &self.drag {
+            if drag.lifted {
+                if let Some(idx) = self.game_tile_at(self.cursor_pos) {
+                    let cell = self.tile_screen_rect(idx).unwrap();
+                    let preview_colour = if self.can_place(idx, drag.tile) {
+                        Vec4::new(0.0, 1.0, 0.0, 0.3)
+                    } else {
+                        Vec4::new(1.0, 0.0, 0.0, 0.3)
+                    };
+                    renderer.draw_rect(cell, preview_colour, 16.0);
+                }
+                let r = Rect::new_centered(self.cursor_pos.x, self.cursor_pos.y, tile_size.x, tile_size.y);
+                renderer.draw_tile(r, drag.tile[0], drag.tile[1], drag.tile[2], drag.tile[3], 20.0, 0.8);
+            }
+        }
+
+        for anim in self.returning.iter() {
+            let t = 1.0 - (1.0 - anim.t) * (1.0 - anim.t);
+            let c = anim.from.centroid().lerp(anim.to.centroid(), t);
+            let size = Vec2::new(anim.from.w, anim.from.h).lerp(Vec2::new(anim.to.w, anim.to.h), t);
+            let r = Rect::new_centered(c.x, c.y, size.x, size.y);
+            renderer.draw_tile(r, anim.tile[0], anim.tile[1], anim.tile[2], anim.tile[3], 20.0, 0.8);
+        }
+
+        // edges of the palette light up when there's more to scroll to
+        if let Some(menu) = self.gui_rect(GUIElement::Menu) {
+            let hint = Vec4::new(1.0, 1.0, 1.0, 0.6);
+            if self.palette_scroll > 0 {
+                renderer.draw_rect(menu.child(0.0, 0.0, 1.0, 0.006), hint, 12.0);
+            }
+            if self.palette_scroll < palette.max_scroll() {
+                renderer.draw_rect(menu.child(0.0, 0.994, 1.0, 0.006), hint, 12.0);
+            }
+        }
+        self.palette = Some(palette);
+
+        if let Some(r) = self.gui_rect(GUIElement::StatusBar) {
+            let (left, right) = self.status_text();
+            let text_colour = colours.text;
+            renderer.draw_text_in(r.child(0.0, 0.0, 0.65, 1.0), &left, Align::Start, aspect_ratio, text_colour, 30.0);
+            renderer.draw_text_in(r.child(0.65, 0.0, 0.35, 1.0), &right, Align::End, aspect_ratio, text_colour, 30.0);
+        }
+        if let (Some(r), Some((msg, tween))) = (self.gui_rect(GUIElement::MessageBar), &self.message) {
+            // hold then fade out over the last bit
+            let alpha = ((1.0 - tween.t()) / 0.3).min(1.0);
+            let colour = Vec4::new(colours.message.x, colours.message.y, colours.message.z, alpha);
+            renderer.draw_text_in(r.dilate(-r.h * 0.2), msg, Align::Centre, aspect_ratio, colour, 30.0);
+        }
     }
 
-    pub fn lmb(&mut self, p: Vec2) {
-        let l = &mut self.levels[self.current_level];
-        let ld = &mut self.level_datas[self.current_level];
+    fn draw_level_select(&mut self, renderer: &mut Renderer, aspect_ratio: f32) {
+        let key = (Screen::LevelSelect, 0, aspect_ratio.to_bits(), 0, 0);
+        if self.gui_key != Some(key) {
+            let gui = level_select_gui(&self.level_datas);
+            self.set_gui(key, gui);
+        }
+
+        let packs = level_packs(&self.level_datas);
+        let colours = self.settings.theme.colours();
+        let text_colour = colours.text;
+        for (elem, r) in self.frame_gui.iter() {
+            match *elem {
+                GUIElement::Background => renderer.draw_rect(*r, colours.background, 1.0),
+                GUIElement::StatusBar => renderer.draw_text_in(*r, "levels", Align::Start, aspect_ratio, text_colour, 30.0),
+                GUIElement::PackHeader(p) => renderer.draw_text_in(r.child(0.0, 0.4, 1.0, 0.45), &packs[p].0, Align::Start, aspect_ratio, text_colour, 30.0),
+                GUIElement::LevelThumb(i) => {
+                    let ld = &self.level_datas[i];
+                    let l = &self.levels[i];
+                    let status = self.progress.status(ld, l);
+                    let highlight = i == self.current_level || self.gui.state(*elem) != WidgetState::Idle;
+                    draw_level_thumbnail(renderer, *r, ld, &l.current_solution, status == LevelStatus::Solved, status == LevelStatus::InProgress, highlight, aspect_ratio);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn draw_settings(&mut self, renderer: &mut Renderer, aspect_ratio: f32) {
+        let key = (Screen::Settings, 0, aspect_ratio.to_bits(), 0, 0);
+        if self.gui_key != Some(key) {
+            self.set_gui(key, settings_gui());
+        }
+
+        let colours = self.settings.theme.colours();
+        for (elem, r) in self.frame_gui.iter() {
+            match *elem {
+                GUIElement::Background => renderer.draw_rect(*r, colours.background, 1.0),
+                GUIElement::StatusBar => renderer.draw_text_in(*r, "settings", Align::Start, aspect_ratio, colours.text, 30.0),
+                GUIElement::SettingRow(i) => {
+                    let (name, value) = self.setting_text(i);
+                    let inner = r.dilate(-r.h * 0.25);
+                    renderer.draw_text_in(inner.child(0.0, 0.0, 0.5, 1.0), &name, Align::Start, aspect_ratio, colours.text, 30.0);
+                    renderer.draw_text_in(inner.child(0.5, 0.0, 0.5, 1.0), &value, Align::End, aspect_ratio, colours.text, 30.0);
+                },
+                _ => {},
+            }
+        }
+    }
 
-        if let Some(idx) = GetClickedGameTile(p, &self.frame_gui) {
-            match self.editing {
-                false => {
-                    if accept(ld.w, ld.h, &l.current_solution, l.selected_tile, idx) {
-                        l.current_solution[idx] = Some(l.selected_tile);
-                    }
+    // buttons and hover tints, the same on every screen
+    fn draw_widgets(&self, renderer: &mut Renderer, aspect_ratio: f32) {
+        for w in self.gui.widgets() {
+            match w.elem {
+                GUIElement::Button(action) => {
+                    let active = match action {
+                        Action::ToggleHints => self.show_hints,
+                        Action::ToggleAutoRotate => self.auto_rotate,
+                        Action::CycleSymmetry => self.levels[self.current_level].symmetry != Symmetry::None,
+                        Action::ToggleMute => self.audio.muted,
+                        _ => false,
+                    };
+                    let base = self.settings.theme.colours().button;
+                    let shade = match self.gui.state(w.elem) {
+                        WidgetState::Idle => 0.0,
+                        WidgetState::Hovered => 0.1,
+                        WidgetState::Pressed => -0.1,
+                    };
+                    let bg = if active {
+                        Vec4::new(base.x + shade + 0.2, base.y + shade + 0.2, base.z + shade, 1.0)
+                    } else {
+                        Vec4::new(base.x + shade, base.y + shade, base.z + shade, 1.0)
+                    };
+                    renderer.draw_rect(w.rect, bg, 25.0);
+                    // the button that opened a screen takes you back from it
+                    let back = matches!((action, self.screen), (Action::LevelSelect, Screen::LevelSelect) | (Action::Settings, Screen::Settings));
+                    let label = if back { "back" } else { button_label(action) };
+                    renderer.draw_text_in(w.rect.dilate(-w.rect.h * 0.25), label, Align::Centre, aspect_ratio, Vec4::new(1.0, 1.0, 1.0, 1.0), 26.0);
                 },
-                true => {
-                    l.current_solution[idx] = Some(l.selected_tile);
-                    ld.fixed_tiles[idx] = Some(l.selected_tile);
+                GUIElement::MenuTile(_) if self.gui.state(w.elem) == WidgetState::Hovered => {
+                    renderer.draw_rect(w.rect, Vec4::new(1.0, 1.0, 1.0, 0.15), 11.0);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn draw_tooltip(&self, renderer: &mut Renderer, aspect_ratio: f32) {
+        if let Some((text, p)) = self.gui.tooltip() {
+            let char_h = 0.025;
+            let size = Renderer::text_size(text, char_h, aspect_ratio);
+            let pad = char_h * 0.4;
+            let w = size.x + 2.0 * pad / aspect_ratio;
+            let h = size.y + 2.0 * pad;
+            // below right of the cursor, kept on screen
+            let x = (p.x + 0.015).min(1.0 - w);
+            let y = if p.y + 0.03 + h > 1.0 { p.y - 0.01 - h } else { p.y + 0.03 };
+            renderer.draw_rect(Rect::new(x, y, w, h), Vec4::new(0.1, 0.1, 0.1, 0.9), 40.0);
+            renderer.draw_text(text, x + pad / aspect_ratio, y + pad, char_h, aspect_ratio, Vec4::new(1.0, 1.0, 1.0, 1.0), 41.0);
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.anims.update(dt);
+        self.gui.update(dt);
+        if let Some((_, tween)) = &mut self.message {
+            tween.update(dt);
+            if tween.done() {
+                self.message = None;
+            }
+        }
+        for anim in self.returning.iter_mut() {
+            anim.t = (anim.t + dt / RETURN_TIME).min(1.0);
+        }
+        let l = &mut self.levels[self.current_level];
+        for anim in self.returning.iter().filter(|x| x.t >= 1.0) {
+            if let Some(idx) = anim.restore {
+                if l.current_solution[idx].is_none() {
+                    l.current_solution[idx] = Some(anim.tile);
+                }
+            }
+        }
+        self.returning.retain(|x| x.t < 1.0);
+    }
+
+    pub fn cursor_moved(&mut self, p: Vec2) {
+        if let (Some(last), Some(board)) = (self.panning, self.gui_rect(GUIElement::GameBoard)) {
+            self.camera.pan(board, p - last);
+            self.panning = Some(p);
+        }
+        self.cursor_pos = p;
+        self.gui.cursor_moved(p);
+
+        if self.stroke.is_some() {
+            self.continue_stroke(p);
+        }
+
+        let start_drag = match &self.drag {
+            Some(drag) => !drag.lifted && (p - drag.start).magnitude() > DRAG_THRESHOLD,
+            None => false,
+        };
+        if start_drag {
+            let drag = self.drag.as_mut().unwrap();
+            drag.lifted = true;
+            if let DragSource::Board(idx) = drag.source {
+                let l = &mut self.levels[self.current_level];
+                l.checkpoint(&self.level_datas[self.current_level]);
+                l.current_solution[idx] = None;
+            }
+        }
+    }
+
+    pub fn lmb(&mut self, p: Vec2) {
+        self.finish_returns();
+
+        // buttons act on release, so they can be backed out of
+        if let Some(GUIElement::Button(_)) = self.gui.press() {
+            return;
+        }
+
+        if self.painting && self.game_tile_at(p).is_some() {
+            self.begin_stroke(StrokeKind::Paint, p);
+            return;
+        }
+
+        if let Some(idx) = self.game_tile_at(p) {
+            let l = &self.levels[self.current_level];
+            let ld = &self.level_datas[self.current_level];
+            match l.current_solution[idx] {
+                // placed tiles can be picked up, so wait for release to tell a click from a drag
+                Some(tile) if !self.editing && ld.fixed_tiles[idx].is_none() => {
+                    self.drag = Some(Drag {source: DragSource::Board(idx), tile, start: p, lifted: false});
                 },
+                _ => self.place(idx),
+            }
+        }
+
+        if let Some(GUIElement::MenuTile(idx)) = self.gui.hit(p) {
+            self.select_tile(idx);
+            let tile = self.levels[self.current_level].selected_tile;
+            self.drag = Some(Drag {source: DragSource::Palette(idx), tile, start: p, lifted: false});
+        }
+    }
+    pub fn lmb_release(&mut self, p: Vec2) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        if !drag.lifted {
+            if let DragSource::Board(idx) = drag.source {
+                self.place(idx);
+            }
+            return;
+        }
+
+        let target = self.game_tile_at(p);
+        if let Some(idx) = target {
+            if self.can_place(idx, drag.tile) {
+                // board drags already checkpointed when the tile was lifted
+                let from_palette = matches!(drag.source, DragSource::Palette(_));
+                if from_palette {
+                    self.levels[self.current_level].checkpoint(&self.level_datas[self.current_level]);
+                }
+                if self.put_tile(idx, Some(drag.tile)) {
+                    return;
+                }
+                if from_palette {
+                    self.levels[self.current_level].history.pop();
+                }
             }
         }
 
-        if let Some(idx) = GetClickedMenuTile(p, &self.frame_gui) {
-            l.selected_tile_idx = idx as i32;
-            l.selected_tile = ld.tile_choices[idx];
+        let (to, restore) = match drag.source {
+            DragSource::Board(idx) => {
+                self.levels[self.current_level].history.pop();
+                (self.tile_screen_rect(idx), Some(idx))
+            },
+            DragSource::Palette(idx) => (self.gui_rect(GUIElement::MenuTile(idx)), None),
+        };
+        if let (Some(cell), Some(to)) = (self.tile_screen_rect(0), to) {
+            let from = Rect::new_centered(p.x, p.y, cell.w, cell.h);
+            self.returning.push(ReturnAnim {tile: drag.tile, from, to, t: 0.0, restore});
+        } else if let Some(idx) = restore {
+            self.levels[self.current_level].current_solution[idx] = Some(drag.tile);
         }
     }
     pub fn rmb(&mut self, p: Vec2) {
+        if self.painting {
+            self.begin_stroke(StrokeKind::Erase, p);
+            return;
+        }
+
+        if let Some(idx) = self.game_tile_at(p) {
+            self.remove(idx);
+        }
+    }
+
+    fn begin_stroke(&mut self, kind: StrokeKind, p: Vec2) {
+        self.levels[self.current_level].checkpoint(&self.level_datas[self.current_level]);
+        self.stroke = Some(Stroke {kind, last_pos: p, changed: false});
+        self.stroke_at(p);
+    }
+
+    fn continue_stroke(&mut self, p: Vec2) {
+        let last_pos = self.stroke.as_ref().unwrap().last_pos;
+        // sample along the way so quick sweeps dont skip cells
+        let step = self.tile_screen_rect(0).map(|r| r.w.min(r.h) * 0.25).unwrap_or(0.01);
+        let n = ((p - last_pos).magnitude() / step).ceil().max(1.0) as usize;
+        for i in 1..=n {
+            self.stroke_at(last_pos.lerp(p, i as f32 / n as f32));
+        }
+        self.stroke.as_mut().unwrap().last_pos = p;
+    }
+
+    fn stroke_at(&mut self, p: Vec2) {
+        let idx = match self.game_tile_at(p) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let kind = self.stroke.as_ref().unwrap().kind;
+        let current = self.levels[self.current_level].current_solution[idx];
+        let tile = match kind {
+            StrokeKind::Paint => {
+                let tile = self.levels[self.current_level].selected_tile;
+                if current == Some(tile) || !self.can_place(idx, tile) {
+                    return;
+                }
+                Some(tile)
+            },
+            StrokeKind::Erase => {
+                if !self.can_remove(idx) {
+                    return;
+                }
+                None
+            },
+        };
+        if self.put_tile(idx, tile) {
+            self.stroke.as_mut().unwrap().changed = true;
+        }
+    }
+
+    fn end_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take() {
+            if !stroke.changed {
+                self.levels[self.current_level].history.pop();
+            }
+        }
+    }
+
+    fn gui_rect(&self, elem: GUIElement) -> Option<Rect> {
+        self.gui.rect(elem)
+    }
+
+    // board tiles are laid out unzoomed, so picking goes back through the camera
+    fn game_tile_at(&self, p: Vec2) -> Option<usize> {
+        let board = self.gui_rect(GUIElement::GameBoard).filter(|r| r.contains(p))?;
+        match self.gui.hit(self.camera.to_world(board, p)) {
+            Some(GUIElement::GameTile(idx)) => Some(idx),
+            _ => None,
+        }
+    }
+
+    // where a board tile is on screen with the camera applied
+    fn tile_screen_rect(&self, idx: usize) -> Option<Rect> {
+        let board = self.gui_rect(GUIElement::GameBoard)?;
+        self.gui_rect(GUIElement::GameTile(idx)).map(|r| self.camera.rect_to_screen(board, r))
+    }
+
+    // zoom in (positive) or out around the cursor, or the middle of the board if it's elsewhere
+    pub fn zoom_board(&mut self, steps: i32) {
+        if let Some(board) = self.gui_rect(GUIElement::GameBoard) {
+            let p = if board.contains(self.cursor_pos) { self.cursor_pos } else { board.centroid() };
+            self.camera.zoom_at(board, p, ZOOM_STEP.powi(steps));
+        }
+    }
+
+    // snap any tiles still sliding back so the board is settled before the next change
+    fn finish_returns(&mut self) {
+        for anim in self.returning.iter_mut() {
+            anim.t = 1.0;
+        }
+        self.update(0.0);
+    }
+
+    // put back anything in hand or in flight, eg before switching level
+    fn settle(&mut self) {
+        self.end_stroke();
+        self.panning = None;
+        if let Some(drag) = self.drag.take() {
+            if let (DragSource::Board(idx), true) = (drag.source, drag.lifted) {
+                let l = &mut self.levels[self.current_level];
+                l.history.pop();
+                l.current_solution[idx] = Some(drag.tile);
+            }
+        }
+        self.finish_returns();
+    }
+
+    fn can_place(&self, idx: usize, tile: Tile) -> bool {
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        self.editing || (ld.fixed_tiles[idx].is_none() && fits(ld.w, ld.h, &l.current_solution, tile, idx))
+    }
+
+    fn set_tile(&mut self, idx: usize, tile: Option<Tile>) {
         let l = &mut self.levels[self.current_level];
         let ld = &mut self.level_datas[self.current_level];
+        let was_full = l.current_solution.iter().all(|x| x.is_some());
+        self.anims.tile_changed(idx, l.current_solution[idx], tile);
+        match (l.current_solution[idx], tile) {
+            (_, Some(_)) => self.audio.play(Sound::Place),
+            (Some(_), None) => self.audio.play(Sound::Remove),
+            (None, None) => {},
+        }
+        l.current_solution[idx] = tile;
+        if self.editing {
+            ld.fixed_tiles[idx] = tile;
+            l.unsaved = true;
+        } else if !was_full && l.current_solution.iter().all(|x| x.is_some()) {
+            self.anims.level_complete();
+            self.audio.play(Sound::Complete);
+            if l.is_solved(ld) {
+                self.save_progress();
+            }
+        }
+    }
 
-        if let Some(idx) = GetClickedGameTile(p, &self.frame_gui) {
-            match self.editing {
-                false => {
-                    if ld.fixed_tiles[idx].is_none() {
-                        l.current_solution[idx] = None;
-                    }
+    pub fn place(&mut self, idx: usize) {
+        // fixed tiles are only changed in edit mode
+        if !self.editing && self.level_datas[self.current_level].fixed_tiles[idx].is_some() {
+            return;
+        }
+        let mut tile = self.levels[self.current_level].selected_tile;
+        if self.auto_rotate && !self.editing {
+            match self.auto_rotation(idx, tile) {
+                Some(rotated) => {
+                    tile = rotated;
+                    self.levels[self.current_level].selected_tile = rotated;
+                },
+                None => {
+                    self.reject(idx, "doesn't fit any way round".to_owned());
+                    return;
                 },
-                true => {
-                    l.current_solution[idx] = None;
-                    ld.fixed_tiles[idx] = None;
+            }
+        }
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        // eg cycling a cell where only one way round fits
+        if l.current_solution[idx] == Some(tile) {
+            return;
+        }
+        let reason = if self.editing { None } else { reject_reason(ld.w, ld.h, &l.current_solution, tile, idx) };
+        if let Some(reason) = reason {
+            self.reject(idx, format!("doesn't fit: {}", reason));
+            return;
+        }
+        self.levels[self.current_level].checkpoint(&self.level_datas[self.current_level]);
+        if !self.put_tile(idx, Some(tile)) {
+            self.levels[self.current_level].history.pop();
+            self.reject(idx, "doesn't fit: symmetric copy".to_owned());
+        }
+    }
+
+    fn reject(&mut self, idx: usize, msg: String) {
+        self.notify(msg);
+        self.anims.rejected(idx);
+        self.audio.play(Sound::Reject);
+    }
+
+    // every change to the board goes through here so the symmetric cells follow. copies
+    // go in where they're legal, except when the level requires symmetry, then a copy
+    // that can't go in stops the whole placement and nothing changes
+    fn put_tile(&mut self, idx: usize, tile: Option<Tile>) -> bool {
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        // copies are checked against the board as it will be, eg next to the original
+        let mut board = l.current_solution.clone();
+        board[idx] = tile;
+        let mut copies = Vec::new();
+        for (i, t) in l.symmetry.images(ld.w, ld.h, idx, tile.unwrap_or([Vec3::new(0.0, 0.0, 0.0); 4])) {
+            let t = tile.map(|_| t);
+            let legal = match t {
+                None => self.can_remove(i),
+                Some(t) => {
+                    // reflections might not be a tile the player actually has
+                    let in_palette = ld.tile_choices.iter().any(|c| tile_rotations(*c).contains(&t));
+                    self.editing || (ld.fixed_tiles[i].is_none() && in_palette && fits(ld.w, ld.h, &board, t, i))
                 },
+            };
+            if legal {
+                board[i] = t;
+                copies.push((i, t));
+            } else if t.is_some() && ld.require_symmetry && !self.editing {
+                return false;
+            }
+        }
+        self.set_tile(idx, tile);
+        for (i, t) in copies {
+            self.set_tile(i, t);
+        }
+        true
+    }
+
+    // the orientation to place when auto rotating: as shown if it fits, otherwise the
+    // first that does. clicking a cell already holding one of them cycles to the next
+    fn auto_rotation(&self, idx: usize, tile: Tile) -> Option<Tile> {
+        let fits: Vec<Tile> = tile_rotations(tile).into_iter().filter(|t| self.can_place(idx, *t)).collect();
+        let current = self.levels[self.current_level].current_solution[idx];
+        match current.and_then(|c| fits.iter().position(|t| *t == c)) {
+            Some(i) => Some(fits[(i + 1) % fits.len()]),
+            None => fits.first().copied(),
+        }
+    }
+
+    fn can_remove(&self, idx: usize) -> bool {
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        l.current_solution[idx].is_some() && (self.editing || ld.fixed_tiles[idx].is_none())
+    }
+
+    pub fn remove(&mut self, idx: usize) {
+        if self.can_remove(idx) {
+            self.levels[self.current_level].checkpoint(&self.level_datas[self.current_level]);
+            self.put_tile(idx, None);
+        }
+    }
+    pub fn select_tile(&mut self, idx: usize) {
+        let l = &mut self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+
+        l.selected_tile_idx = idx as i32;
+        l.selected_tile = ld.tile_choices[idx];
+        self.audio.play(Sound::Select);
+        self.reveal_selection = true;
+    }
+
+    // the wheel scrolls the palette rather than doing its usual thing while this is true
+    pub fn palette_under_cursor(&self) -> bool {
+        let over_menu = self.gui_rect(GUIElement::Menu).map(|r| r.contains(self.cursor_pos)).unwrap_or(false);
+        let overflowing = self.palette.as_ref().map(|p| p.max_scroll() > 0).unwrap_or(false);
+        self.screen == Screen::Game && over_menu && overflowing
+    }
+
+    fn scroll_palette(&mut self, rows: i32) {
+        if let Some(palette) = &self.palette {
+            let scroll = self.palette_scroll as i32 + rows;
+            self.palette_scroll = scroll.clamp(0, palette.max_scroll() as i32) as usize;
+        }
+    }
+
+    // switch to a level and back to the board, progress on the old one is kept
+    pub fn open_level(&mut self, idx: usize) {
+        self.settle();
+        self.save_progress();
+        self.current_level = idx;
+        self.camera = Camera::new();
+        self.anims.clear();
+        self.palette_scroll = 0;
+        self.reveal_selection = true;
+        self.screen = Screen::Game;
+        self.notify_level();
+    }
+
+    fn save_progress(&mut self) {
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        self.progress.record(ld, l);
+        if let Some(path) = &self.progress_path {
+            if let Err(e) = self.progress.save(path) {
+                let msg = format!("couldn't save progress to {}: {}", path, e);
+                self.notify(msg);
+            }
+        }
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    // volume lives in Audio while playing, copied over whenever settings are saved
+    fn save_settings(&mut self) {
+        self.settings.volume = self.audio.volume;
+        self.settings.muted = self.audio.muted;
+        if let Some(path) = &self.settings_path {
+            if let Err(e) = self.settings.save(path) {
+                let msg = format!("couldn't save settings to {}: {}", path, e);
+                self.notify(msg);
+            }
+        }
+    }
+
+    // name and current value for a row of the settings screen
+    pub fn setting_text(&self, row: usize) -> (String, String) {
+        let s = &self.settings;
+        let on_off = |x: bool| if x { "on".to_owned() } else { "off".to_owned() };
+        match row {
+            0 => ("window".to_owned(), match s.window_mode {
+                WindowMode::Windowed => "windowed".to_owned(),
+                WindowMode::Borderless => "fullscreen".to_owned(),
+            }),
+            1 => ("size".to_owned(), format!("{}x{}", s.width, s.height)),
+            2 => ("vsync".to_owned(), on_off(s.vsync)),
+            3 => ("frame cap".to_owned(), if s.frame_cap == 0 { "none".to_owned() } else { format!("{} fps", s.frame_cap) }),
+            4 => ("volume".to_owned(), if self.audio.muted { "muted".to_owned() } else { format!("{:.0}%", self.audio.volume * 100.0) }),
+            5 => ("theme".to_owned(), format!("{:?}", s.theme).to_lowercase()),
+            6 => ("colours".to_owned(), format!("{:?}", s.colour_vision).to_lowercase()),
+            7 => ("edge symbols".to_owned(), on_off(s.edge_symbols)),
+            8 => ("tiles".to_owned(), format!("{:?}", s.tile_style).to_lowercase()),
+            9 => ("fog".to_owned(), on_off(s.fog > 0.0)),
+            10 => ("vignette".to_owned(), on_off(s.vignette > 0.0)),
+            11 => ("pixels".to_owned(), if s.pixel_size <= 1 { "off".to_owned() } else { format!("{}x", s.pixel_size) }),
+            _ => (String::new(), String::new()),
+        }
+    }
+
+    // shown along the bottom for a bit, replaces whatever was there
+    fn notify(&mut self, msg: String) {
+        self.message = Some((msg, Tween::new(MESSAGE_TIME)));
+    }
+
+    fn notify_level(&mut self) {
+        let ld = &self.level_datas[self.current_level];
+        let msg = format!("{} - {}", self.current_level + 1, ld.name);
+        self.notify(msg);
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_ref().map(|(msg, _)| msg.as_str())
+    }
+
+    // left and right halves of the line above the board
+    pub fn status_text(&self) -> (String, String) {
+        let l = &self.levels[self.current_level];
+        let ld = &self.level_datas[self.current_level];
+        let left = format!("{}/{}  {}", self.current_level + 1, self.levels.len(), ld.name);
+        let right = if self.editing {
+            if l.unsaved { "editing - unsaved".to_owned() } else { "editing - saved".to_owned() }
+        } else if l.is_solved(ld) {
+            "solved!".to_owned()
+        } else {
+            let filled = l.current_solution.iter().filter(|x| x.is_some()).count();
+            format!("{}/{}", filled, ld.w * ld.h)
+        };
+        (left, right)
+    }
+
+    // hand this frame's sounds to whatever is playing them
+    pub fn present_audio(&mut self, backend: &mut dyn AudioBackend) {
+        self.audio.present(backend);
+    }
+
+    pub fn level_data(&self) -> &LevelData {
+        &self.level_datas[self.current_level]
+    }
+    pub fn level(&self) -> &Level {
+        &self.levels[self.current_level]
+    }
+    pub fn level_index(&self) -> usize {
+        self.current_level
+    }
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+    pub fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    pub fn undo(&mut self) {
+        // a lifted tile or open stroke is finished before the board is rewound
+        self.settle();
+        let l = &mut self.levels[self.current_level];
+        let ld = &mut self.level_datas[self.current_level];
+
+        if let Some((solution, fixed_tiles)) = l.history.pop() {
+            l.current_solution = solution;
+            if ld.fixed_tiles != fixed_tiles {
+                l.unsaved = true;
             }
+            ld.fixed_tiles = fixed_tiles;
         }
     }
-    pub fn key_press(&mut self, key: VirtualKeyCode) {
-        match key {
-            VirtualKeyCode::M => {
-                if self.current_level < self.levels.len() - 1 {
-                    self.current_level += 1;
+
+    pub fn apply(&mut self, action: Action) {
+        // menu screens only take clicks and the actions that aren't about the board
+        if self.screen != Screen::Game {
+            match action {
+                Action::Primary => {
+                    self.gui.press();
+                    return;
+                },
+                Action::LevelSelect | Action::Settings | Action::VolumeUp | Action::VolumeDown | Action::ToggleMute |
+                Action::CycleWindowMode | Action::CycleResolution | Action::ToggleVsync | Action::CycleFrameCap |
+                Action::CycleTheme | Action::CycleColourVision | Action::ToggleEdgeSymbols | Action::CycleTileStyle |
+                Action::ToggleFog | Action::ToggleVignette | Action::CyclePixelSize | Action::Quit => {},
+                _ => return,
+            }
+        }
+
+        match action {
+            Action::Primary => self.lmb(self.cursor_pos),
+            Action::Secondary => self.rmb(self.cursor_pos),
+            Action::SelectTile(idx) => {
+                if idx < self.level_datas[self.current_level].tile_choices.len() {
+                    self.select_tile(idx);
                 }
-                let ld = &self.level_datas[self.current_level];
-                println!("{} - {}", self.current_level, ld.name)
             },
-            VirtualKeyCode::N => {
-                if self.current_level > 0 {
-                    self.current_level -= 1;
+            Action::NextTile | Action::PrevTile => {
+                // in the order the palette shows them
+                let order = palette_order(&self.level_datas[self.current_level].tile_choices);
+                let n = order.len();
+                if n > 0 {
+                    let current = self.levels[self.current_level].selected_tile_idx as usize;
+                    let slot = order.iter().position(|i| *i == current).unwrap_or(0);
+                    let slot = if action == Action::NextTile { (slot + 1) % n } else { (slot + n - 1) % n };
+                    self.select_tile(order[slot]);
                 }
-                let ld = &self.level_datas[self.current_level];
-                println!("{} - {}", self.current_level, ld.name)
             },
-            VirtualKeyCode::Q => {
+            Action::RotateCcw => {
                 self.levels[self.current_level].selected_tile = self.levels[self.current_level].selected_tile.rotate_ccw();
+                self.anims.rotated(std::f32::consts::FRAC_PI_2);
+                self.audio.play(Sound::Rotate);
+                if let Some(drag) = &mut self.drag {
+                    drag.tile = drag.tile.rotate_ccw();
+                }
             },
-            VirtualKeyCode::E => {
+            Action::RotateCw => {
                 self.levels[self.current_level].selected_tile = self.levels[self.current_level].selected_tile.rotate_cw();
+                self.anims.rotated(-std::f32::consts::FRAC_PI_2);
+                self.audio.play(Sound::Rotate);
+                if let Some(drag) = &mut self.drag {
+                    drag.tile = drag.tile.rotate_cw();
+                }
+            },
+            Action::Place(idx) | Action::Remove(idx) if idx >= self.level_datas[self.current_level].w * self.level_datas[self.current_level].h => {},
+            Action::Place(idx) => self.place(idx),
+            Action::Remove(idx) => self.remove(idx),
+            Action::NextLevel => self.open_level((self.current_level + 1).min(self.levels.len() - 1)),
+            Action::PrevLevel => self.open_level(self.current_level.saturating_sub(1)),
+            Action::ToggleEdit => {
+                self.editing = !self.editing;
+                self.notify(format!("editing {}", if self.editing { "on" } else { "off" }));
+            },
+            Action::TogglePaint => {
+                self.settle();
+                self.painting = !self.painting;
+                self.notify(format!("paint mode {}", if self.painting { "on" } else { "off" }));
+            },
+            Action::ToggleAutoRotate => {
+                self.auto_rotate = !self.auto_rotate;
+                self.notify(format!("auto rotate {}", if self.auto_rotate { "on" } else { "off" }));
+            },
+            Action::ToggleHints => {
+                self.show_hints = !self.show_hints;
             },
-            VirtualKeyCode::P => {
+            Action::CycleSymmetry => {
+                let ld = &self.level_datas[self.current_level];
+                let l = &mut self.levels[self.current_level];
+                l.symmetry = l.symmetry.next();
+                if ld.require_symmetry && l.symmetry == Symmetry::None {
+                    l.symmetry = l.symmetry.next();
+                }
+                // authors set the level's symmetry in edit mode
                 if self.editing {
-                    println!("editing off");
-                    self.editing = false;
+                    self.level_datas[self.current_level].symmetry = l.symmetry;
+                    l.unsaved = true;
+                }
+                let msg = format!("symmetry {:?}", l.symmetry);
+                self.notify(msg);
+            },
+            Action::VolumeUp | Action::VolumeDown => {
+                let step = if action == Action::VolumeUp { 0.1 } else { -0.1 };
+                self.audio.set_volume(self.audio.volume + step);
+                self.audio.muted = false;
+                self.save_settings();
+                self.notify(format!("volume {:.0}%", self.audio.volume * 100.0));
+            },
+            Action::ToggleMute => {
+                self.audio.muted = !self.audio.muted;
+                self.save_settings();
+                self.notify(format!("sound {}", if self.audio.muted { "off" } else { "on" }));
+            },
+            Action::Settings => {
+                if self.screen == Screen::Settings {
+                    self.screen = Screen::Game;
                 } else {
-                    println!("editing on");
-                    self.editing = true;
+                    self.settle();
+                    self.screen = Screen::Settings;
                 }
             },
-            VirtualKeyCode::S => {
+            Action::CycleWindowMode => {
+                self.settings.window_mode = match self.settings.window_mode {
+                    WindowMode::Windowed => WindowMode::Borderless,
+                    WindowMode::Borderless => WindowMode::Windowed,
+                };
+                self.save_settings();
+            },
+            Action::CycleResolution => {
+                self.settings.next_resolution();
+                self.save_settings();
+            },
+            Action::ToggleVsync => {
+                self.settings.vsync = !self.settings.vsync;
+                self.save_settings();
+                self.notify("vsync changes after a restart".to_owned());
+            },
+            Action::CycleFrameCap => {
+                self.settings.next_frame_cap();
+                self.save_settings();
+            },
+            Action::CycleTheme => {
+                self.settings.theme = self.settings.theme.next();
+                self.save_settings();
+            },
+            Action::CycleColourVision => {
+                self.settings.colour_vision = self.settings.colour_vision.next();
+                self.save_settings();
+            },
+            Action::ToggleEdgeSymbols => {
+                self.settings.edge_symbols = !self.settings.edge_symbols;
+                self.save_settings();
+            },
+            Action::CycleTileStyle => {
+                self.settings.tile_style = self.settings.tile_style.next();
+                self.save_settings();
+            },
+            Action::ToggleFog => {
+                self.settings.fog = if self.settings.fog > 0.0 { 0.0 } else { DEFAULT_FOG };
+                self.save_settings();
+            },
+            Action::ToggleVignette => {
+                self.settings.vignette = if self.settings.vignette > 0.0 { 0.0 } else { DEFAULT_VIGNETTE };
+                self.save_settings();
+            },
+            Action::CyclePixelSize => {
+                self.settings.next_pixel_size();
+                self.save_settings();
+            },
+            Action::Save => {
                 if self.editing {
-                    println!("saving level");
                     self.level_datas[self.current_level].save();
+                    self.levels[self.current_level].unsaved = false;
+                    self.notify("saved".to_owned());
+                } else {
+                    self.notify("editing is off".to_owned());
+                }
+            },
+            Action::Undo => self.undo(),
+            Action::Export => {
+                let l = &self.levels[self.current_level];
+                let ld = &self.level_datas[self.current_level];
+                let path = format!("{}{}.png", SCREENSHOT_PATH, ld.name);
+                let res = fs::create_dir_all(SCREENSHOT_PATH)
+                    .and_then(|_| export_board_png(ld, &l.current_solution, ld.w as u32 * 128, ld.h as u32 * 128, &path));
+                let msg = match res {
+                    Ok(()) => format!("exported {}", path),
+                    Err(e) => format!("export failed: {}", e),
+                };
+                self.notify(msg);
+            },
+            Action::LevelSelect => {
+                if self.screen == Screen::LevelSelect {
+                    self.screen = Screen::Game;
+                } else {
+                    self.settle();
+                    self.save_progress();
+                    self.screen = Screen::LevelSelect;
+                }
+            },
+            Action::Pan => self.panning = Some(self.cursor_pos),
+            Action::PanLeft | Action::PanRight | Action::PanUp | Action::PanDown => {
+                if let Some(board) = self.gui_rect(GUIElement::GameBoard) {
+                    let delta = match action {
+                        Action::PanLeft => Vec2::new(board.w, 0.0),
+                        Action::PanRight => Vec2::new(-board.w, 0.0),
+                        Action::PanUp => Vec2::new(0.0, board.h),
+                        _ => Vec2::new(0.0, -board.h),
+                    };
+                    self.camera.pan(board, delta * PAN_STEP);
+                }
+            },
+            Action::ZoomIn => self.zoom_board(1),
+            Action::ZoomOut => self.zoom_board(-1),
+            Action::ScrollPaletteUp => self.scroll_palette(-1),
+            Action::ScrollPaletteDown => self.scroll_palette(1),
+            // the frontend does the actual quitting
+            Action::Quit => self.save_progress(),
+        }
+    }
+
+    // for inputs that have a release, eg mouse buttons
+    pub fn release(&mut self, action: Action) {
+        // a pan can be let go of anywhere, eg after the button that opened a menu
+        if action == Action::Pan {
+            self.panning = None;
+            return;
+        }
+        if self.screen != Screen::Game {
+            if action == Action::Primary {
+                match self.gui.release() {
+                    Some(GUIElement::LevelThumb(i)) => self.open_level(i),
+                    Some(GUIElement::Button(action)) => self.apply(action),
+                    _ => {},
+                }
+            }
+            return;
+        }
+
+        match action {
+            Action::Primary => {
+                let clicked = self.gui.release();
+                if self.stroke.is_some() {
+                    self.end_stroke();
                 } else {
-                    println!("editing is off");
+                    self.lmb_release(self.cursor_pos);
+                }
+                if let Some(GUIElement::Button(action)) = clicked {
+                    self.apply(action);
                 }
             },
+            Action::Secondary => self.end_stroke(),
             _ => {},
         }
     }
 }
 
+fn button_label(action: Action) -> &'static str {
+    match action {
+        Action::LevelSelect => "list",
+        Action::Settings => "opts",
+        Action::CycleWindowMode | Action::CycleResolution | Action::CycleFrameCap | Action::CycleTheme |
+        Action::CycleColourVision | Action::CycleTileStyle | Action::CyclePixelSize => "change",
+        Action::ToggleVsync | Action::ToggleEdgeSymbols | Action::ToggleFog | Action::ToggleVignette => "toggle",
+        Action::VolumeDown => "-",
+        Action::VolumeUp => "+",
+        Action::ToggleMute => "mute",
+        Action::Undo => "undo",
+        Action::ToggleHints => "hint",
+        Action::ToggleAutoRotate => "auto",
+        Action::CycleSymmetry => "sym",
+        _ => "?",
+    }
+}
+
+fn tooltip(elem: GUIElement) -> Option<String> {
+    let text = match elem {
+        GUIElement::Button(Action::LevelSelect) => "pick a level",
+        GUIElement::Button(Action::Settings) => "settings",
+        GUIElement::Button(Action::Undo) => "undo the last change",
+        GUIElement::Button(Action::ToggleHints) => "show where tiles fit",
+        GUIElement::Button(Action::ToggleAutoRotate) => "turn tiles to fit when placing",
+        GUIElement::Button(Action::CycleSymmetry) => "mirror placements",
+        GUIElement::MenuTile(i) => return Some(format!("tile {}", i + 1)),
+        GUIElement::LevelThumb(i) => return Some(format!("level {}", i + 1)),
+        _ => return None,
+    };
+    Some(text.to_owned())
+}
+
 pub fn load_level_data() -> Vec<LevelData> {
-    let mut entries: Vec<String> = fs::read_dir(LEVEL_PATH).unwrap()
+    let entries: Vec<String> = fs::read_dir(LEVEL_PATH).unwrap()
         .filter_map(|res| res.ok())
         .map(|res| res.path().into_os_string().into_string().unwrap())
         .collect();
@@ -164,4 +1345,497 @@ pub fn load_level_data() -> Vec<LevelData> {
     level_datas.sort_by_key(|x| (level_priorities.get(&x.name as &str).unwrap_or(&99999.0) * 10000.0) as u64);
     
     level_datas
-}
\ No newline at end of file
+}
+#[test]
+fn test_apply_and_undo() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut application = Application::with_levels(vec![LevelData::new("undo".to_owned(), 2, 1, vec![[r, r, g, g]])]);
+    let idx = application.level_data().fixed_tiles.iter().position(|x| x.is_none()).unwrap();
+    let before = application.level().current_solution.clone();
+
+    application.apply(Action::ToggleEdit);
+    application.apply(Action::SelectTile(0));
+    application.apply(Action::RotateCw);
+    application.apply(Action::Place(idx));
+    let placed = application.level().selected_tile;
+    assert_eq!(application.level().current_solution[idx], Some(placed));
+    assert_eq!(application.level_data().fixed_tiles[idx], Some(placed));
+
+    application.apply(Action::Undo);
+    assert_eq!(application.level().current_solution, before);
+    assert!(application.level_data().fixed_tiles[idx].is_none());
+
+    // off the board is ignored
+    let n = application.level().current_solution.len();
+    application.apply(Action::Place(n));
+    application.apply(Action::Remove(n));
+    assert_eq!(application.level().current_solution, before);
+}
+
+#[test]
+fn test_drag_from_palette() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut application = Application::with_levels(vec![LevelData::new("drag".to_owned(), 2, 1, vec![[r, r, g, g]])]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    application.apply(Action::ToggleEdit);
+
+    let idx = application.level_data().fixed_tiles.iter().position(|x| x.is_none()).unwrap();
+    let palette = application.gui_rect(GUIElement::MenuTile(0)).unwrap().centroid();
+    let cell = application.gui_rect(GUIElement::GameTile(idx)).unwrap().centroid();
+
+    // dropping off the board slides back and places nothing
+    let before = application.level().current_solution.clone();
+    application.cursor_moved(palette);
+    application.apply(Action::Primary);
+    application.cursor_moved(Vec2::new(0.99, 0.01));
+    application.release(Action::Primary);
+    assert_eq!(application.returning.len(), 1);
+    application.update(1.0);
+    assert!(application.returning.is_empty());
+    assert_eq!(application.level().current_solution, before);
+
+    application.cursor_moved(palette);
+    application.apply(Action::Primary);
+    application.cursor_moved(cell);
+    application.apply(Action::RotateCw);
+    application.release(Action::Primary);
+    let tile = application.level_data().tile_choices[0].rotate_cw();
+    assert_eq!(application.level().current_solution[idx], Some(tile));
+}
+
+#[test]
+fn test_drag_onto_fixed() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut ld = LevelData::new("fixed".to_owned(), 2, 1, vec![[r, r, g, g]]);
+    ld.fixed_tiles[0] = Some([g, g, g, g]);
+    let mut application = Application::with_levels(vec![ld]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+
+    // the palette tile would fit there with the right cell empty, but fixed tiles stay put
+    let palette = application.gui_rect(GUIElement::MenuTile(0)).unwrap().centroid();
+    let cell = application.gui_rect(GUIElement::GameTile(0)).unwrap().centroid();
+    application.cursor_moved(palette);
+    application.apply(Action::Primary);
+    application.cursor_moved(cell);
+    application.release(Action::Primary);
+    application.update(1.0);
+    assert_eq!(application.level().current_solution[0], Some([g, g, g, g]));
+    assert_eq!(application.level_data().fixed_tiles[0], Some([g, g, g, g]));
+}
+
+#[test]
+fn test_undo_while_dragging() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("undo".to_owned(), 2, 1, vec![[r, r, r, r]]);
+    let mut application = Application::with_levels(vec![ld]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    application.apply(Action::Place(0));
+
+    // undo mid drag puts the tile back and rewinds the placement, the drop does nothing
+    application.cursor_moved(application.gui_rect(GUIElement::GameTile(0)).unwrap().centroid());
+    application.apply(Action::Primary);
+    application.cursor_moved(application.gui_rect(GUIElement::GameTile(1)).unwrap().centroid());
+    application.apply(Action::Undo);
+    application.release(Action::Primary);
+    application.update(1.0);
+    assert_eq!(application.level().current_solution, vec![None, None]);
+    assert!(application.level().history.is_empty());
+}
+
+#[test]
+fn test_undo_while_painting() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("undo".to_owned(), 2, 1, vec![[r, r, r, r]]);
+    let mut application = Application::with_levels(vec![ld]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    application.apply(Action::Place(0));
+    application.apply(Action::TogglePaint);
+
+    // a stroke that hasnt changed anything yet, undo should only take back the placement
+    application.cursor_moved(application.gui_rect(GUIElement::GameTile(0)).unwrap().centroid());
+    application.apply(Action::Primary);
+    application.apply(Action::Undo);
+    application.release(Action::Primary);
+    assert_eq!(application.level().current_solution, vec![None, None]);
+    assert!(application.level().history.is_empty());
+}
+
+#[test]
+fn test_paint_stroke() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut application = Application::with_levels(vec![LevelData::new("paint".to_owned(), 3, 2, vec![[r, r, g, g]])]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    application.apply(Action::ToggleEdit);
+    application.apply(Action::TogglePaint);
+    application.apply(Action::SelectTile(0));
+
+    let ld = application.level_data();
+    let (w, h) = (ld.w, ld.h);
+    let start = application.gui_rect(GUIElement::GameTile(0)).unwrap().centroid();
+    let end = application.gui_rect(GUIElement::GameTile(w - 1)).unwrap().centroid();
+    let history = application.level().history.len();
+
+    // one sweep along the top row fills every cell of it
+    application.cursor_moved(start);
+    application.apply(Action::Primary);
+    application.cursor_moved(end);
+    application.release(Action::Primary);
+    let tile = application.level().selected_tile;
+    assert!(application.level().current_solution[..w].iter().all(|x| *x == Some(tile)));
+    assert_eq!(application.level().history.len(), history + 1);
+
+    // erase down the first column
+    let bottom = application.gui_rect(GUIElement::GameTile((h - 1) * w)).unwrap().centroid();
+    application.cursor_moved(start);
+    application.apply(Action::Secondary);
+    application.cursor_moved(bottom);
+    application.release(Action::Secondary);
+    assert!((0..h).all(|j| application.level().current_solution[j * w].is_none()));
+    assert_eq!(application.level().history.len(), history + 2);
+
+    application.apply(Action::Undo);
+    assert_eq!(application.level().current_solution[0], Some(tile));
+
+    // in play a stroke across a fixed cell paints around it
+    let mut ld = LevelData::new("fixed".to_owned(), 2, 1, vec![[r, r, g, g]]);
+    ld.fixed_tiles[0] = Some([g, g, g, g]);
+    let mut application = Application::with_levels(vec![ld]);
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    application.apply(Action::TogglePaint);
+    application.apply(Action::SelectTile(0));
+    application.cursor_moved(application.gui_rect(GUIElement::GameTile(0)).unwrap().centroid());
+    application.apply(Action::Primary);
+    application.cursor_moved(application.gui_rect(GUIElement::GameTile(1)).unwrap().centroid());
+    application.release(Action::Primary);
+    assert_eq!(application.level().current_solution, vec![Some([g, g, g, g]), Some([r, r, g, g])]);
+}
+
+// the left cell is fixed with a red east edge, so the right cell needs red on its west
+#[cfg(test)]
+fn two_cell_app() -> Application {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut ld = LevelData::new("two cell".to_owned(), 2, 1, vec![[r, r, g, g]]);
+    ld.fixed_tiles[0] = Some([g, r, g, g]);
+    Application::with_levels(vec![ld])
+}
+
+#[test]
+fn test_auto_rotate() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut application = two_cell_app();
+
+    // shown orientation has green west, no fit without auto rotate
+    application.apply(Action::Place(1));
+    assert!(application.level().current_solution[1].is_none());
+
+    application.apply(Action::ToggleAutoRotate);
+    application.apply(Action::Place(1));
+    let first = application.level().current_solution[1].unwrap();
+    assert_eq!(first[3], r);
+    assert_eq!(application.level().selected_tile, first);
+
+    // both orientations with red on the west fit, clicking again cycles between them
+    application.apply(Action::Place(1));
+    let second = application.level().current_solution[1].unwrap();
+    assert_eq!(second[3], r);
+    assert_ne!(first, second);
+    application.apply(Action::Place(1));
+    assert_eq!(application.level().current_solution[1], Some(first));
+
+    // the fixed cell isn't cycled, and a cycle that comes back round isn't an undo step
+    let history = application.level().history.len();
+    application.apply(Action::Place(0));
+    assert_eq!(application.level().current_solution[0], Some([g, r, g, g]));
+    assert_eq!(application.level().history.len(), history);
+
+    let mut application = Application::with_levels(vec![LevelData::new("plain".to_owned(), 1, 1, vec![[r, r, r, r]])]);
+    application.apply(Action::ToggleAutoRotate);
+    application.apply(Action::Place(0));
+    application.apply(Action::Place(0));
+    assert_eq!(application.level().history.len(), 1);
+}
+
+#[test]
+fn test_symmetric_placement() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let mut ld = LevelData::new("sym".to_owned(), 3, 1, vec![[r, r, g, g]]);
+    ld.symmetry = Symmetry::MirrorH;
+    let mut application = Application::with_levels(vec![ld]);
+
+    // [r, r, g, g] mirrored is [r, g, g, r], which is one of its rotations
+    application.apply(Action::Place(0));
+    assert_eq!(application.level().current_solution[2], Some([r, g, g, r]));
+    application.apply(Action::Undo);
+    assert_eq!(application.level().current_solution, vec![None; 3]);
+
+    application.apply(Action::Place(2));
+    application.apply(Action::Remove(0));
+    assert_eq!(application.level().current_solution, vec![None; 3]);
+
+    // drops and paint strokes get copies too
+    application.draw(&mut Renderer::new(), 4.0 / 3.0);
+    let cell = |application: &Application, idx| application.gui_rect(GUIElement::GameTile(idx)).unwrap().centroid();
+    application.cursor_moved(application.gui_rect(GUIElement::MenuTile(0)).unwrap().centroid());
+    application.apply(Action::Primary);
+    application.cursor_moved(cell(&application, 0));
+    application.release(Action::Primary);
+    assert_eq!(application.level().current_solution[2], Some([r, g, g, r]));
+    application.apply(Action::Undo);
+    assert_eq!(application.level().current_solution, vec![None; 3]);
+
+    application.apply(Action::TogglePaint);
+    application.cursor_moved(cell(&application, 2));
+    application.apply(Action::Primary);
+    application.release(Action::Primary);
+    assert_eq!(application.level().current_solution[0], Some([r, g, g, r]));
+    application.apply(Action::Secondary);
+    application.release(Action::Secondary);
+    assert_eq!(application.level().current_solution, vec![None; 3]);
+
+    // a copy that can't go in is skipped, unless the level requires symmetry
+    let mut ld = LevelData::new("sym".to_owned(), 3, 1, vec![[r, r, g, g]]);
+    ld.symmetry = Symmetry::MirrorH;
+    ld.fixed_tiles[2] = Some([g, g, g, g]);
+    let mut application = Application::with_levels(vec![ld.clone()]);
+    application.apply(Action::Place(0));
+    assert_eq!(application.level().current_solution[0], Some([r, r, g, g]));
+
+    ld.require_symmetry = true;
+    let mut application = Application::with_levels(vec![ld]);
+    application.apply(Action::Place(0));
+    assert!(application.level().current_solution[0].is_none());
+    assert!(application.level().history.is_empty());
+}
+
+#[test]
+fn test_animation_triggers() {
+    let mut application = two_cell_app();
+
+    application.apply(Action::Place(1));
+    assert!(application.anims.shake_offset(1) == 0.0);
+    application.update(0.05);
+    assert!(application.anims.shake_offset(1) != 0.0);
+
+    application.apply(Action::RotateCw);
+    application.apply(Action::RotateCw);
+    assert!(application.anims.ghost_angle() < 0.0);
+    application.apply(Action::Place(1));
+    assert!(application.anims.cell(1).is_some());
+    assert!(application.anims.sweep().is_some());
+
+    application.update(2.0);
+    assert!(application.anims.is_idle());
+}
+
+#[test]
+fn test_sounds() {
+    let mut application = two_cell_app();
+    let mut backend = RecordingAudio::new();
+
+    application.apply(Action::SelectTile(0));
+    application.apply(Action::Place(1));
+    application.apply(Action::RotateCw);
+    application.apply(Action::RotateCw);
+    application.apply(Action::Place(1));
+    application.present_audio(&mut backend);
+    assert_eq!(backend.sounds(), vec![Sound::Select, Sound::Reject, Sound::Rotate, Sound::Place, Sound::Complete]);
+
+    backend.played.clear();
+    application.apply(Action::Remove(1));
+    application.apply(Action::ToggleMute);
+    application.apply(Action::Place(1));
+    application.present_audio(&mut backend);
+    assert!(backend.played.is_empty());
+
+    application.apply(Action::VolumeDown);
+    assert!(!application.audio.muted);
+    application.apply(Action::Remove(1));
+    application.present_audio(&mut backend);
+    assert_eq!(backend.sounds(), vec![Sound::Remove]);
+    assert!((application.audio.volume - 0.7).abs() < 0.001);
+}
+
+#[test]
+fn test_status_text() {
+    let mut application = two_cell_app();
+
+    assert_eq!(application.status_text(), ("1/1  two cell".to_owned(), "1/2".to_owned()));
+    application.apply(Action::Place(1));
+    assert_eq!(application.message(), Some("doesn't fit: left edge neighbour"));
+    application.update(MESSAGE_TIME);
+    assert_eq!(application.message(), None);
+
+    application.apply(Action::RotateCw);
+    application.apply(Action::RotateCw);
+    application.apply(Action::Place(1));
+    assert_eq!(application.status_text().1, "solved!");
+
+    application.apply(Action::ToggleEdit);
+    assert_eq!(application.status_text().1, "editing - saved");
+    application.apply(Action::Remove(1));
+    assert_eq!(application.status_text().1, "editing - unsaved");
+}
+
+#[test]
+fn test_palette_scroll() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("palette".to_owned(), 2, 2, vec![[r; 4]; 200]);
+    let mut application = Application::with_levels(vec![ld]);
+    let mut renderer = Renderer::new();
+    application.draw(&mut renderer, 4.0 / 3.0);
+
+    // wheel over the board is left for rotating
+    application.cursor_moved(Vec2::new(0.6, 0.5));
+    assert!(!application.palette_under_cursor());
+
+    let menu = application.gui_rect(GUIElement::Menu).unwrap();
+    application.cursor_moved(menu.centroid());
+    assert!(application.palette_under_cursor());
+    application.apply(Action::ScrollPaletteDown);
+    assert_eq!(application.palette_scroll, 1);
+    for _ in 0..5 {
+        application.apply(Action::ScrollPaletteUp);
+    }
+    assert_eq!(application.palette_scroll, 0);
+
+    application.apply(Action::SelectTile(199));
+    application.draw(&mut renderer, 4.0 / 3.0);
+    assert!(application.palette_scroll > 0);
+    assert!(application.gui_rect(GUIElement::SelectionIndicator).is_some());
+}
+
+#[test]
+fn test_toolbar_buttons() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("buttons".to_owned(), 2, 1, vec![[r; 4]]);
+    let mut application = Application::with_levels(vec![ld]);
+    let mut renderer = Renderer::new();
+    application.draw(&mut renderer, 4.0 / 3.0);
+
+    let hints = application.gui_rect(GUIElement::Button(Action::ToggleHints)).unwrap();
+    application.cursor_moved(hints.centroid());
+    application.apply(Action::Primary);
+    assert!(!application.show_hints);
+    application.release(Action::Primary);
+    assert!(application.show_hints);
+
+    // backing out of a press doesn't click
+    application.apply(Action::Primary);
+    application.cursor_moved(Vec2::new(0.6, 0.5));
+    application.release(Action::Primary);
+    assert!(application.show_hints);
+
+    // layout is kept until something it depends on changes
+    let before = application.gui_key;
+    application.draw(&mut renderer, 4.0 / 3.0);
+    assert_eq!(application.gui_key, before);
+    application.draw(&mut renderer, 16.0 / 9.0);
+    assert_ne!(application.gui_key, before);
+}
+
+#[test]
+fn test_level_select() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let mut lds: Vec<LevelData> = (0..3).map(|i| LevelData::new(format!("select{}", i), 2, 1, vec![[r; 4]])).collect();
+    lds[2].pack = "extra".to_owned();
+    let mut application = Application::with_levels(lds);
+    let mut renderer = Renderer::new();
+
+    application.apply(Action::Place(0));
+    application.apply(Action::Pan);
+    application.apply(Action::LevelSelect);
+    assert_eq!(application.screen(), Screen::LevelSelect);
+    // a pan held while switching screens is let go of
+    assert!(application.panning.is_none());
+    // board actions don't reach the level behind
+    application.apply(Action::Place(1));
+    assert!(application.level().current_solution[1].is_none());
+    assert!(application.progress.levels.contains_key("select0"));
+
+    application.draw(&mut renderer, 4.0 / 3.0);
+    assert!(application.gui_rect(GUIElement::PackHeader(1)).is_some());
+    let thumb = application.gui_rect(GUIElement::LevelThumb(2)).unwrap();
+    application.cursor_moved(thumb.centroid());
+    application.apply(Action::Primary);
+    application.release(Action::Primary);
+    assert_eq!(application.screen(), Screen::Game);
+    assert_eq!(application.level_index(), 2);
+
+    // the first level's board is still as it was left
+    application.apply(Action::LevelSelect);
+    application.open_level(0);
+    assert!(application.level().current_solution[0].is_some());
+}
+
+#[test]
+fn test_zoomed_picking() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("zoom".to_owned(), 4, 4, vec![[r; 4]]);
+    let mut application = Application::with_levels(vec![ld]);
+    let mut renderer = Renderer::new();
+    application.draw(&mut renderer, 4.0 / 3.0);
+
+    // zooming in on a tile leaves it under the cursor and blows it up
+    let tile = application.gui_rect(GUIElement::GameTile(5)).unwrap();
+    let p = tile.centroid();
+    application.cursor_moved(p);
+    for _ in 0..3 {
+        application.apply(Action::ZoomIn);
+    }
+    assert_eq!(application.game_tile_at(p), Some(5));
+    let zoomed = application.tile_screen_rect(5).unwrap();
+    assert!(zoomed.w > tile.w * 1.5);
+    // where the next tile along used to be is still this one
+    assert_eq!(application.game_tile_at(Vec2::new(p.x + tile.w * 0.75, p.y)), Some(5));
+
+    // middle drag moves the view, clicks go where the tiles are drawn
+    application.apply(Action::Pan);
+    application.cursor_moved(Vec2::new(p.x + tile.w * 0.25, p.y));
+    application.release(Action::Pan);
+    let moved = application.tile_screen_rect(5).unwrap();
+    assert!((moved.x - zoomed.x - tile.w * 0.25).abs() < 0.0001);
+    application.apply(Action::PanRight);
+    let cell = application.tile_screen_rect(5).unwrap();
+    assert!(cell.x < moved.x);
+    application.cursor_moved(cell.centroid());
+    application.apply(Action::Primary);
+    application.release(Action::Primary);
+    assert!(application.level().current_solution[5].is_some());
+}
+
+#[test]
+fn test_settings_screen() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let ld = LevelData::new("settings".to_owned(), 2, 1, vec![[r; 4]]);
+    let mut application = Application::with_levels(vec![ld]);
+    let mut renderer = Renderer::new();
+
+    let mut click = |application: &mut Application, elem: GUIElement| {
+        application.draw(&mut renderer, 4.0 / 3.0);
+        let p = application.gui_rect(elem).unwrap().centroid();
+        application.cursor_moved(p);
+        application.apply(Action::Primary);
+        application.release(Action::Primary);
+    };
+
+    click(&mut application, GUIElement::Button(Action::Settings));
+    assert_eq!(application.screen(), Screen::Settings);
+
+    click(&mut application, GUIElement::Button(Action::CycleTheme));
+    assert_eq!(application.settings().theme, Theme::Dark);
+    click(&mut application, GUIElement::Button(Action::VolumeDown));
+    assert_eq!(application.setting_text(4), ("volume".to_owned(), "70%".to_owned()));
+    assert!((application.settings().volume - 0.7).abs() < 0.0001);
+    click(&mut application, GUIElement::Button(Action::CycleFrameCap));
+    assert_eq!(application.settings().frame_cap, 120);
+
+    click(&mut application, GUIElement::Button(Action::Settings));
+    assert_eq!(application.screen(), Screen::Game);
+}
@@ -0,0 +1,234 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::f32::consts::PI;
+
+/*
+
+Sound effects. Application queues up Sounds as things happen and game.rs hands
+them to an AudioBackend once a frame, same split as Renderer and RenderBackend.
+
+Effects are synthesised rather than loaded: a stone grinding sound is low passed
+noise, chimes are a few decaying sines. Build with --features audio for actual
+output through rodio.
+
+*/
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Select,
+    Rotate,
+    Place,
+    Reject,
+    Remove,
+    Complete,
+}
+
+pub trait AudioBackend {
+    // volume already has master volume applied
+    fn play(&mut self, sound: Sound, volume: f32);
+}
+
+pub struct NullAudio;
+
+impl AudioBackend for NullAudio {
+    fn play(&mut self, _sound: Sound, _volume: f32) {}
+}
+
+// keeps everything it's asked to play, for tests
+#[derive(Default)]
+pub struct RecordingAudio {
+    pub played: Vec<(Sound, f32)>,
+}
+
+impl RecordingAudio {
+    pub fn new() -> RecordingAudio {
+        RecordingAudio {played: Vec::new()}
+    }
+
+    pub fn sounds(&self) -> Vec<Sound> {
+        self.played.iter().map(|(s, _)| *s).collect()
+    }
+}
+
+impl AudioBackend for RecordingAudio {
+    fn play(&mut self, sound: Sound, volume: f32) {
+        self.played.push((sound, volume));
+    }
+}
+
+pub struct Audio {
+    pub volume: f32,
+    pub muted: bool,
+    queue: Vec<Sound>,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Audio {
+    pub fn new() -> Audio {
+        Audio {volume: 0.8, muted: false, queue: Vec::new()}
+    }
+
+    // one of each per frame is plenty, eg symmetric placements
+    pub fn play(&mut self, sound: Sound) {
+        if !self.queue.contains(&sound) {
+            self.queue.push(sound);
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn present(&mut self, backend: &mut dyn AudioBackend) {
+        for sound in self.queue.drain(..) {
+            if !self.muted && self.volume > 0.0 {
+                backend.play(sound, self.volume * sound_gain(sound));
+            }
+        }
+    }
+}
+
+// rough mix so the frequent clicks sit under the placements
+fn sound_gain(sound: Sound) -> f32 {
+    match sound {
+        Sound::Select => 0.3,
+        Sound::Rotate => 0.4,
+        Sound::Place => 0.8,
+        Sound::Reject => 0.5,
+        Sound::Remove => 0.6,
+        Sound::Complete => 0.7,
+    }
+}
+
+fn envelope(t: f32, attack: f32, decay: f32) -> f32 {
+    if t < attack {
+        t / attack
+    } else {
+        (-(t - attack) / decay).exp()
+    }
+}
+
+// low passed noise with a falling cutoff, sounds like stone dragging on stone
+fn grind(samples: &mut [f32], sample_rate: u32, seed: u64, cutoff_start: f32, cutoff_end: f32, decay: f32) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut lp = 0.0;
+    let n = samples.len() as f32;
+    for (i, s) in samples.iter_mut().enumerate() {
+        let t = i as f32 / sample_rate as f32;
+        let cutoff = cutoff_start + (cutoff_end - cutoff_start) * (i as f32 / n);
+        let a = 1.0 - (-2.0 * PI * cutoff / sample_rate as f32).exp();
+        lp += (rng.gen_range(-1.0..1.0) - lp) * a;
+        *s += lp * 3.0 * envelope(t, 0.01, decay);
+    }
+}
+
+fn tone(samples: &mut [f32], sample_rate: u32, start: f32, freq: f32, amp: f32, decay: f32) {
+    let start_idx = (start * sample_rate as f32) as usize;
+    for (i, s) in samples.iter_mut().enumerate().skip(start_idx) {
+        let t = (i - start_idx) as f32 / sample_rate as f32;
+        *s += (2.0 * PI * freq * t).sin() * amp * envelope(t, 0.005, decay);
+    }
+}
+
+pub fn synth(sound: Sound, sample_rate: u32) -> Vec<f32> {
+    let len = |secs: f32| (secs * sample_rate as f32) as usize;
+    let mut samples;
+    match sound {
+        Sound::Select => {
+            samples = vec![0.0; len(0.08)];
+            tone(&mut samples, sample_rate, 0.0, 880.0, 0.5, 0.02);
+        },
+        Sound::Rotate => {
+            samples = vec![0.0; len(0.15)];
+            grind(&mut samples, sample_rate, 1, 2000.0, 800.0, 0.05);
+        },
+        Sound::Place => {
+            samples = vec![0.0; len(0.35)];
+            grind(&mut samples, sample_rate, 2, 1200.0, 200.0, 0.1);
+            tone(&mut samples, sample_rate, 0.0, 90.0, 0.6, 0.08);
+        },
+        Sound::Reject => {
+            samples = vec![0.0; len(0.25)];
+            tone(&mut samples, sample_rate, 0.0, 140.0, 0.5, 0.06);
+            tone(&mut samples, sample_rate, 0.08, 110.0, 0.5, 0.06);
+        },
+        Sound::Remove => {
+            samples = vec![0.0; len(0.25)];
+            grind(&mut samples, sample_rate, 3, 400.0, 1500.0, 0.08);
+        },
+        Sound::Complete => {
+            samples = vec![0.0; len(1.5)];
+            for (i, freq) in [523.25, 659.25, 783.99, 1046.5].iter().enumerate() {
+                tone(&mut samples, sample_rate, i as f32 * 0.12, *freq, 0.3, 0.5);
+            }
+        },
+    }
+    for s in samples.iter_mut() {
+        *s = s.clamp(-1.0, 1.0);
+    }
+    samples
+}
+
+#[cfg(feature = "audio")]
+pub struct RodioAudio {
+    _stream: rodio::OutputStream,
+    handle: rodio::OutputStreamHandle,
+    cache: std::collections::HashMap<Sound, Vec<f32>>,
+}
+
+#[cfg(feature = "audio")]
+impl RodioAudio {
+    // None if there's no output device, game just stays quiet
+    pub fn new() -> Option<RodioAudio> {
+        let (stream, handle) = rodio::OutputStream::try_default().ok()?;
+        Some(RodioAudio {_stream: stream, handle, cache: std::collections::HashMap::new()})
+    }
+}
+
+#[cfg(feature = "audio")]
+impl AudioBackend for RodioAudio {
+    fn play(&mut self, sound: Sound, volume: f32) {
+        let samples: Vec<f32> = self.cache.entry(sound)
+            .or_insert_with(|| synth(sound, SAMPLE_RATE))
+            .iter().map(|x| x * volume).collect();
+        if let Err(e) = self.handle.play_raw(rodio::buffer::SamplesBuffer::new(1, SAMPLE_RATE, samples)) {
+            eprintln!("couldn't play {:?}: {}", sound, e);
+        }
+    }
+}
+
+#[test]
+fn test_audio_queue() {
+    let mut audio = Audio::new();
+    let mut backend = RecordingAudio::new();
+    audio.play(Sound::Place);
+    audio.play(Sound::Place);
+    audio.play(Sound::Complete);
+    audio.present(&mut backend);
+    assert_eq!(backend.sounds(), vec![Sound::Place, Sound::Complete]);
+
+    audio.present(&mut backend);
+    assert_eq!(backend.played.len(), 2);
+
+    audio.muted = true;
+    audio.play(Sound::Select);
+    audio.present(&mut backend);
+    assert_eq!(backend.played.len(), 2);
+}
+
+#[test]
+fn test_synth() {
+    for sound in [Sound::Select, Sound::Rotate, Sound::Place, Sound::Reject, Sound::Remove, Sound::Complete] {
+        let samples = synth(sound, SAMPLE_RATE);
+        assert!(!samples.is_empty());
+        assert!(samples.iter().all(|x| x.abs() <= 1.0));
+        assert!(samples.iter().any(|x| x.abs() > 0.05), "{:?} is silent", sound);
+    }
+}
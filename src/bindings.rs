@@ -0,0 +1,222 @@
+use wang::application::*;
+use glutin::event::{MouseButton, MouseScrollDelta, VirtualKeyCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+
+/*
+
+Key and mouse bindings, loaded from bindings.json at startup:
+
+{
+    "RotateCw": ["E", "WheelDown"],
+    "Primary": ["MouseLeft"],
+    "SelectTile(0)": ["Key1"]
+}
+
+Keys use glutin's VirtualKeyCode names. Mouse inputs are MouseLeft, MouseRight,
+MouseMiddle, WheelUp and WheelDown, CtrlWheelUp and CtrlWheelDown for the wheel
+with ctrl held, and PaletteWheelUp and PaletteWheelDown for the wheel over a
+palette with more tiles than fit. Ctrl wins if both apply. Actions listed replace
+their default bindings, actions not listed keep them. Invalid entries are reported
+and skipped.
+
+*/
+
+pub const BINDINGS_PATH: &str = "./bindings.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    WheelUp,
+    WheelDown,
+    CtrlWheelUp,
+    CtrlWheelDown,
+    PaletteWheelUp,
+    PaletteWheelDown,
+}
+
+impl Input {
+    pub fn parse(s: &str) -> Result<Input, String> {
+        match s {
+            "MouseLeft" => Ok(Input::Mouse(MouseButton::Left)),
+            "MouseRight" => Ok(Input::Mouse(MouseButton::Right)),
+            "MouseMiddle" => Ok(Input::Mouse(MouseButton::Middle)),
+            "WheelUp" => Ok(Input::WheelUp),
+            "WheelDown" => Ok(Input::WheelDown),
+            "CtrlWheelUp" => Ok(Input::CtrlWheelUp),
+            "CtrlWheelDown" => Ok(Input::CtrlWheelDown),
+            "PaletteWheelUp" => Ok(Input::PaletteWheelUp),
+            "PaletteWheelDown" => Ok(Input::PaletteWheelDown),
+            _ => serde_json::from_value::<VirtualKeyCode>(Value::String(s.to_owned()))
+                .map(Input::Key)
+                .map_err(|_| format!("unknown key or mouse input {:?}", s)),
+        }
+    }
+
+    pub fn from_wheel(delta: MouseScrollDelta, ctrl: bool, over_palette: bool) -> Option<Input> {
+        let y = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y as f64,
+            MouseScrollDelta::PixelDelta(pos) => pos.y,
+        };
+        if y == 0.0 {
+            return None;
+        }
+        Some(match (y > 0.0, ctrl, over_palette) {
+            (true, true, _) => Input::CtrlWheelUp,
+            (false, true, _) => Input::CtrlWheelDown,
+            (true, false, true) => Input::PaletteWheelUp,
+            (false, false, true) => Input::PaletteWheelDown,
+            (true, false, false) => Input::WheelUp,
+            (false, false, false) => Input::WheelDown,
+        })
+    }
+}
+
+pub struct Bindings {
+    inputs: HashMap<Input, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let mut inputs = HashMap::new();
+        inputs.insert(Input::Key(VirtualKeyCode::M), Action::NextLevel);
+        inputs.insert(Input::Key(VirtualKeyCode::N), Action::PrevLevel);
+        inputs.insert(Input::Key(VirtualKeyCode::Q), Action::RotateCcw);
+        inputs.insert(Input::Key(VirtualKeyCode::E), Action::RotateCw);
+        inputs.insert(Input::Key(VirtualKeyCode::P), Action::ToggleEdit);
+        inputs.insert(Input::Key(VirtualKeyCode::B), Action::TogglePaint);
+        inputs.insert(Input::Key(VirtualKeyCode::R), Action::ToggleAutoRotate);
+        inputs.insert(Input::Key(VirtualKeyCode::H), Action::ToggleHints);
+        inputs.insert(Input::Key(VirtualKeyCode::Y), Action::CycleSymmetry);
+        inputs.insert(Input::Key(VirtualKeyCode::Equals), Action::VolumeUp);
+        inputs.insert(Input::Key(VirtualKeyCode::Minus), Action::VolumeDown);
+        inputs.insert(Input::Key(VirtualKeyCode::V), Action::ToggleMute);
+        inputs.insert(Input::Key(VirtualKeyCode::S), Action::Save);
+        inputs.insert(Input::Key(VirtualKeyCode::X), Action::Export);
+        inputs.insert(Input::Key(VirtualKeyCode::L), Action::LevelSelect);
+        inputs.insert(Input::Key(VirtualKeyCode::O), Action::Settings);
+        inputs.insert(Input::Key(VirtualKeyCode::F11), Action::CycleWindowMode);
+        inputs.insert(Input::Key(VirtualKeyCode::C), Action::ToggleEdgeSymbols);
+        inputs.insert(Input::Key(VirtualKeyCode::Left), Action::PanLeft);
+        inputs.insert(Input::Key(VirtualKeyCode::Right), Action::PanRight);
+        inputs.insert(Input::Key(VirtualKeyCode::Up), Action::PanUp);
+        inputs.insert(Input::Key(VirtualKeyCode::Down), Action::PanDown);
+        inputs.insert(Input::Key(VirtualKeyCode::PageUp), Action::ZoomIn);
+        inputs.insert(Input::Key(VirtualKeyCode::PageDown), Action::ZoomOut);
+        inputs.insert(Input::CtrlWheelUp, Action::ZoomIn);
+        inputs.insert(Input::CtrlWheelDown, Action::ZoomOut);
+        inputs.insert(Input::Key(VirtualKeyCode::Z), Action::Undo);
+        inputs.insert(Input::Key(VirtualKeyCode::Escape), Action::Quit);
+        inputs.insert(Input::Mouse(MouseButton::Left), Action::Primary);
+        inputs.insert(Input::Mouse(MouseButton::Right), Action::Secondary);
+        inputs.insert(Input::Mouse(MouseButton::Middle), Action::Pan);
+        inputs.insert(Input::WheelUp, Action::RotateCcw);
+        inputs.insert(Input::WheelDown, Action::RotateCw);
+        inputs.insert(Input::PaletteWheelUp, Action::ScrollPaletteUp);
+        inputs.insert(Input::PaletteWheelDown, Action::ScrollPaletteDown);
+
+        Bindings {inputs}
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, input: Input) -> Option<Action> {
+        self.inputs.get(&input).copied()
+    }
+
+    // falls back to the defaults if the file is missing
+    pub fn load() -> Bindings {
+        match fs::read_to_string(BINDINGS_PATH) {
+            Ok(contents) => {
+                let (bindings, errors) = Bindings::parse(&contents);
+                for e in errors {
+                    eprintln!("{}: {}", BINDINGS_PATH, e);
+                }
+                bindings
+            },
+            Err(_) => Bindings::default(),
+        }
+    }
+
+    pub fn parse(json_str: &str) -> (Bindings, Vec<String>) {
+        let mut bindings = Bindings::default();
+        let mut errors = Vec::new();
+
+        let entries = match serde_json::from_str::<HashMap<String, Vec<String>>>(json_str) {
+            Ok(entries) => entries,
+            Err(e) => {
+                errors.push(format!("expected an object of \"Action\": [\"Input\", ...], {}", e));
+                return (bindings, errors);
+            },
+        };
+
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort();
+
+        let mut bound: HashMap<Input, Action> = HashMap::new();
+        for (action_str, input_strs) in entries {
+            let action = match action_str.parse::<Action>() {
+                Ok(action) => action,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                },
+            };
+
+            bindings.inputs.retain(|_, a| *a != action);
+            for input_str in input_strs {
+                let input = match Input::parse(&input_str) {
+                    Ok(input) => input,
+                    Err(e) => {
+                        errors.push(format!("{} (bound to {})", e, action_str));
+                        continue;
+                    },
+                };
+                if let Some(other) = bound.get(&input) {
+                    errors.push(format!("{} is bound to both {:?} and {:?}, using {:?}", input_str, other, action, action));
+                }
+                bound.insert(input, action);
+                bindings.inputs.insert(input, action);
+            }
+        }
+
+        (bindings, errors)
+    }
+}
+
+#[test]
+fn test_parse_bindings() {
+    let (bindings, errors) = Bindings::parse(r#"{
+        "RotateCw": ["E", "WheelUp"],
+        "SelectTile(1)": ["Key2"],
+        "Jump": ["Space"],
+        "Undo": ["Nope"]
+    }"#);
+
+    assert_eq!(bindings.get(Input::WheelUp), Some(Action::RotateCw));
+    assert_eq!(bindings.get(Input::Key(VirtualKeyCode::E)), Some(Action::RotateCw));
+    assert_eq!(bindings.get(Input::Key(VirtualKeyCode::Key2)), Some(Action::SelectTile(1)));
+    // untouched actions keep their defaults
+    assert_eq!(bindings.get(Input::Key(VirtualKeyCode::M)), Some(Action::NextLevel));
+    // the default wheel down rotate is gone since RotateCw was rebound
+    assert_eq!(bindings.get(Input::WheelDown), None);
+
+    // ctrl zooms wherever the cursor is
+    let up = MouseScrollDelta::LineDelta(0.0, 1.0);
+    assert_eq!(Input::from_wheel(up, true, true), Some(Input::CtrlWheelUp));
+    assert_eq!(Input::from_wheel(up, false, true), Some(Input::PaletteWheelUp));
+    assert_eq!(bindings.get(Input::CtrlWheelUp), Some(Action::ZoomIn));
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("unknown action \"Jump\""));
+    assert!(errors[1].contains("unknown key or mouse input \"Nope\""));
+}
+
+#[test]
+fn test_shipped_bindings_match_defaults() {
+    let (bindings, errors) = Bindings::parse(&fs::read_to_string(BINDINGS_PATH).unwrap());
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(bindings.inputs, Bindings::default().inputs);
+}
@@ -0,0 +1,120 @@
+use crate::kmath::*;
+use crate::rect::*;
+use crate::renderer::*;
+
+/*
+
+Pan and zoom for the board. The layout still puts the board in the same rect on
+screen, the camera picks which part of the board shows in it. Drawing goes
+through Renderer's top_left/bot_right view clipped to the board rect, and
+picking goes back the other way with to_world.
+
+"world" here is where things would be with no zoom, ie what the layout gave out.
+centre is the middle of the view as a fraction of the board, so it holds up when
+the board rect changes with the window.
+
+*/
+
+pub const MIN_ZOOM: f32 = 1.0;
+pub const MAX_ZOOM: f32 = 8.0;
+// each wheel notch or key press
+pub const ZOOM_STEP: f32 = 1.25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub centre: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {centre: Vec2::new(0.5, 0.5), zoom: 1.0}
+    }
+
+    fn centre_world(&self, board: Rect) -> Vec2 {
+        Vec2::new(board.x + board.w * self.centre.x, board.y + board.h * self.centre.y)
+    }
+
+    pub fn to_world(&self, board: Rect, p: Vec2) -> Vec2 {
+        self.centre_world(board) + (p - board.centroid()) / self.zoom
+    }
+
+    pub fn to_screen(&self, board: Rect, p: Vec2) -> Vec2 {
+        board.centroid() + (p - self.centre_world(board)) * self.zoom
+    }
+
+    pub fn rect_to_screen(&self, board: Rect, r: Rect) -> Rect {
+        let tl = self.to_screen(board, Vec2::new(r.x, r.y));
+        Rect::new(tl.x, tl.y, r.w * self.zoom, r.h * self.zoom)
+    }
+
+    // the world rect that the whole screen shows, for Renderer's top_left and bot_right
+    pub fn view(&self, board: Rect) -> (Vec2, Vec2) {
+        (self.to_world(board, Vec2::new(0.0, 0.0)), self.to_world(board, Vec2::new(1.0, 1.0)))
+    }
+
+    // everything drawn after this goes through the camera and stays inside the board
+    pub fn apply(&self, renderer: &mut Renderer, board: Rect) {
+        let (top_left, bot_right) = self.view(board);
+        renderer.top_left = top_left;
+        renderer.bot_right = bot_right;
+        renderer.clip = Some(board);
+    }
+
+    // drag the board along by delta on screen
+    pub fn pan(&mut self, board: Rect, delta: Vec2) {
+        self.centre.x -= delta.x / self.zoom / board.w;
+        self.centre.y -= delta.y / self.zoom / board.h;
+        self.clamp();
+    }
+
+    // zoom by factor keeping whatever is under p where it is
+    pub fn zoom_at(&mut self, board: Rect, p: Vec2, factor: f32) {
+        let anchor = self.to_world(board, p);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let centre = anchor - (p - board.centroid()) / self.zoom;
+        self.centre = Vec2::new((centre.x - board.x) / board.w, (centre.y - board.y) / board.h);
+        self.clamp();
+    }
+
+    // no looking past the edges of the board
+    fn clamp(&mut self) {
+        let half = 0.5 / self.zoom;
+        self.centre.x = self.centre.x.clamp(half, 1.0 - half);
+        self.centre.y = self.centre.y.clamp(half, 1.0 - half);
+    }
+}
+
+#[test]
+fn test_camera() {
+    let board = Rect::new(0.2, 0.1, 0.6, 0.8);
+    let mut camera = Camera::new();
+    let p = Vec2::new(0.3, 0.7);
+    assert_eq!(camera.to_world(board, p), p);
+
+    // the point under the cursor stays put
+    camera.zoom_at(board, p, 2.0);
+    let back = camera.to_screen(board, camera.to_world(board, p));
+    assert!((back - p).magnitude() < 0.0001);
+    let world = camera.to_world(board, p);
+    let zoomed = camera.to_world(board, Vec2::new(0.3, 0.7));
+    assert!((world - zoomed).magnitude() < 0.0001);
+
+    // renderer's view lands the visible part of the board on the board rect
+    let (top_left, bot_right) = camera.view(board);
+    let dims = bot_right - top_left;
+    let on_screen = Vec2::new((world.x - top_left.x) / dims.x, (world.y - top_left.y) / dims.y);
+    assert!((on_screen - p).magnitude() < 0.0001);
+
+    // panning stops at the edge
+    camera.pan(board, Vec2::new(10.0, 10.0));
+    assert_eq!(camera.centre, Vec2::new(0.25, 0.25));
+    camera.zoom_at(board, p, 0.1);
+    assert_eq!(camera, Camera::new());
+}
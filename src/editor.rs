@@ -17,6 +17,8 @@ hybrid tui editor is the go probably
   * test new
   * test add rrbb
   * test remove rrbb
+  * test sym rot2 require
+  * pack test bonus
 
 */
 
@@ -50,8 +52,8 @@ fn main() {
     io::stdout().flush().unwrap();
     for line in io::stdin().lock().lines() {
 
-        let args: Vec<String> = line.unwrap().split(" ").filter(|x| x.len() > 0).map(|x| x.to_owned()).collect();
-        if args.len() == 0 {
+        let args: Vec<String> = line.unwrap().split(" ").filter(|x| !x.is_empty()).map(|x| x.to_owned()).collect();
+        if args.is_empty() {
             continue;
         }
 
@@ -78,7 +80,7 @@ fn main() {
             "tadd" => {
                 if args.len() == 3 {
                     let name = args[1].clone();
-                    if let Some(mut level) = levels.iter_mut().find(|x| x.name == name) {
+                    if let Some(level) = levels.iter_mut().find(|x| x.name == name) {
                         let tile_str = args[2].clone();
                         if tile_str.len() == 4 {
                             let mut tile = Vec::new();
@@ -102,7 +104,7 @@ fn main() {
             "trem" => {
                 if args.len() == 3 {
                     let name = args[1].clone();
-                    if let Some(mut level) = levels.iter_mut().find(|x| x.name == name) {
+                    if let Some(level) = levels.iter_mut().find(|x| x.name == name) {
                         let tile_str = args[2].clone();
                         if tile_str.len() == 4 {
                             let mut tile = Vec::new();
@@ -128,6 +130,37 @@ fn main() {
                     }
                 }
             },
+            "sym" => {
+                if args.len() == 3 || (args.len() == 4 && args[3] == "require") {
+                    let name = args[1].clone();
+                    if let Some(level) = levels.iter_mut().find(|x| x.name == name) {
+                        if let Some(symmetry) = Symmetry::parse(&args[2]) {
+                            level.symmetry = symmetry;
+                            level.require_symmetry = args.len() == 4 && symmetry != Symmetry::None;
+                            level.save();
+                        } else {
+                            println!("invalid symmetry: {} (none, mirrorh, mirrorv, rot2, rot4)", args[2]);
+                        }
+                    } else {
+                        println!("level {} not found", name);
+                    }
+                } else {
+                    println!("usage: sym <level> <none|mirrorh|mirrorv|rot2|rot4> [require]");
+                }
+            },
+            "pack" => {
+                if args.len() == 3 {
+                    let name = args[1].clone();
+                    if let Some(level) = levels.iter_mut().find(|x| x.name == name) {
+                        level.pack = args[2].clone();
+                        level.save();
+                    } else {
+                        println!("level {} not found", name);
+                    }
+                } else {
+                    println!("usage: pack <level> <pack>");
+                }
+            },
             _ => {println!("invalid command: {}", args[0])},
         }
         
@@ -0,0 +1,65 @@
+/*
+export a level board as a png or svg without opening a window
+
+usage: export [--palette] <levelname> [pixels per tile] [out path]
+
+exports the fixed tiles, eg murals authored in edit mode
+out path ending in .svg writes vector output, otherwise png
+--palette writes a sheet of the level's tile choices instead of the board
+*/
+
+use wang::application::*;
+use wang::raster::*;
+use wang::svg::*;
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let palette = if let Some(idx) = args.iter().position(|x| x == "--palette") {
+        args.remove(idx);
+        true
+    } else {
+        false
+    };
+
+    if args.is_empty() {
+        println!("usage: export [--palette] <levelname> [pixels per tile] [out path]");
+        return;
+    }
+
+    let name = &args[0];
+    let tile_px = match args.get(1).map(|x| x.parse::<u32>()) {
+        None => 128,
+        Some(Ok(px)) if px > 0 => px,
+        Some(_) => {
+            println!("invalid pixels per tile: {}", args[1]);
+            return;
+        },
+    };
+    // palette sheets are svg only so they default to it
+    let default_ext = if palette { "svg" } else { "png" };
+    let path = args.get(2).cloned().unwrap_or_else(|| format!("{}.{}", name, default_ext));
+    let svg = path.ends_with(".svg");
+
+    let levels = load_level_data();
+    let ld = if let Some(ld) = levels.iter().find(|x| &x.name == name) {
+        ld
+    } else {
+        println!("level {} not found", name);
+        return;
+    };
+
+    let res = match (palette, svg) {
+        (true, true) => palette_svg(&ld.tile_choices, 4, tile_px as f32).save(&path),
+        (true, false) => {
+            println!("palette sheets are svg only");
+            return;
+        },
+        (false, true) => board_svg(ld, &ld.fresh_solution(), tile_px as f32).save(&path),
+        (false, false) => export_board_png(ld, &ld.fresh_solution(), ld.w as u32 * tile_px, ld.h as u32 * tile_px, &path),
+    };
+
+    match res {
+        Ok(()) => println!("exported {}", path),
+        Err(e) => println!("export failed: {}", e),
+    }
+}
@@ -3,71 +3,76 @@
 use glow::*;
 use std::error::Error;
 use glam::{Mat4};
-use wang::kmath::*;
 use wang::renderer::*;
-use wang::rect::*;
-use wang::level::*;
+use wang::gl_backend::*;
+use wang::shader::*;
 use wang::application::*;
+use wang::audio::*;
+use wang::viewport::*;
+use wang::settings::*;
 use std::collections::HashSet;
+use std::rc::Rc;
 use std::time::{Duration, SystemTime};
 
-fn main() -> Result<(), Box<dyn Error>> {
 
-    let mut window_x = 1600.0;
-    let mut window_y = 1200.0;
+mod bindings;
+use bindings::*;
+
+// quiet unless built with the audio feature and there's a device to play on
+fn audio_backend() -> Box<dyn AudioBackend> {
+    #[cfg(feature = "audio")]
+    if let Some(backend) = RodioAudio::new() {
+        return Box::new(backend);
+    }
+    Box::new(NullAudio)
+}
+
+fn fullscreen(mode: WindowMode, monitor: glutin::monitor::MonitorHandle) -> Option<glutin::window::Fullscreen> {
+    match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(glutin::window::Fullscreen::Borderless(monitor)),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
 
     let projection_mat = Mat4::orthographic_lh(0.0, 1.0, 1.0, 0.0, 1000.0, 0.0);
-    let projection_inverse = projection_mat.inverse();
 
 
+    let mut application = Application::new();
+    // what the window was last set up with, to spot changes from the settings screen
+    let mut applied = application.settings().clone();
+
     unsafe {
         let event_loop = glutin::event_loop::EventLoop::new();
+        // logical size, glutin scales it up on HiDPI displays
         let window_builder = glutin::window::WindowBuilder::new()
             .with_title("Wang's Garden")
-            //.with_inner_size(glutin::dpi::LogicalSize::new(window_x, window_y));
-            .with_inner_size(glutin::dpi::PhysicalSize::new(window_x, window_y));
+            .with_inner_size(glutin::dpi::LogicalSize::new(applied.width, applied.height))
+            .with_fullscreen(fullscreen(applied.window_mode, event_loop.primary_monitor()));
         let window = glutin::ContextBuilder::new()
-            .with_vsync(true)
+            .with_vsync(applied.vsync)
             .build_windowed(window_builder, &event_loop)
             .unwrap()
             .make_current()
             .unwrap();
-        let gl = glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
+        let gl = Rc::new(glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _));
         gl.enable(DEPTH_TEST);
 
-        let mut renderer = Renderer::new(&gl, window_x/window_y);
-
-        let program = gl.create_program().expect("Cannot create program");
-
-        {   // Shader stuff
-            let shader_version = "#version 410";
-            let shader_sources = [
-                (glow::VERTEX_SHADER, std::fs::read_to_string("src/test.vert")?),
-                (glow::FRAGMENT_SHADER, std::fs::read_to_string("src/test.frag")?),
-            ];
-            let mut shaders = Vec::with_capacity(shader_sources.len());
-            for (shader_type, shader_source) in shader_sources.iter() {
-                let shader = gl
-                    .create_shader(*shader_type)
-                    .expect("Cannot create shader");
-                gl.shader_source(shader, &format!("{}\n{}", shader_version, shader_source));
-                gl.compile_shader(shader);
-                if !gl.get_shader_compile_status(shader) {
-                    panic!("{}", gl.get_shader_info_log(shader));
-                }
-                gl.attach_shader(program, shader);
-                shaders.push(shader);
-            }
-            gl.link_program(program);
-            if !gl.get_program_link_status(program) {
-                panic!("{}", gl.get_program_info_log(program));
-            }
-            for shader in shaders {
-                gl.detach_shader(program, shader);
-                gl.delete_shader(shader);
-            }
-            gl.use_program(Some(program));
-        }
+        let size = window.window().inner_size();
+        let mut viewport = Viewport::new(size.width, size.height, window.window().scale_factor());
+        gl.viewport(0, 0, size.width as i32, size.height as i32);
+
+        let mut renderer = Renderer::new();
+        let mut backend = GlBackend::new(gl.clone());
+
+        // edits to these files show up in the game, see shader.rs
+        let mut shaders = Shaders::new(gl.clone());
+        shaders.add(SCENE, "src/test.vert", "src/test.frag")?;
+        shaders.add(POST, "src/post.vert", "src/post.frag")?;
+        let mut post_target = PostTarget::new(gl.clone(), size.width, size.height);
+        // seconds since start, for the fog to drift
+        let mut time = 0.0f32;
 
         gl.blend_func(SRC_ALPHA, ONE_MINUS_SRC_ALPHA);
         gl.enable(BLEND);
@@ -76,15 +81,12 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 
 
-        let mut application = Application::new();
+        let bindings = Bindings::load();
+        let mut audio = audio_backend();
 
         let mut held_keys: HashSet<glutin::event::VirtualKeyCode> = HashSet::new();
-        let mut lmb = false;
-        let mut normalized_cursor_pos = Vec2::new(0.0, 0.0);
         let mut dt = 1.0f64 / 60f64;
 
-        let mut edit = false;
-
         {
             use glutin::event::{Event, WindowEvent};
             use glutin::event_loop::ControlFlow;
@@ -92,66 +94,97 @@ fn main() -> Result<(), Box<dyn Error>> {
             event_loop.run(move |event, _, control_flow| {
                 *control_flow = ControlFlow::Poll;
 
-                let mut cleanup = || {
-                    renderer.destroy(&gl);
-                    gl.delete_program(program);
-                    *control_flow = ControlFlow::Exit;
-                };
-
                 match event {
-                    Event::LoopDestroyed |
-                    Event::WindowEvent {event: WindowEvent::CloseRequested, ..} |
-                    Event::WindowEvent {event: WindowEvent::KeyboardInput {
-                        input: glutin::event::KeyboardInput { virtual_keycode: Some(glutin::event::VirtualKeyCode::Escape), ..}, ..}, ..}
-                    => {
-                        cleanup();
+                    // quitting just sets Exit, this runs once as the loop goes away
+                    Event::LoopDestroyed => {
+                        application.apply(Action::Quit);
+                        backend.destroy();
+                        post_target.destroy();
+                        shaders.destroy();
+                    },
+                    Event::WindowEvent {event: WindowEvent::CloseRequested, ..} => {
+                        *control_flow = ControlFlow::Exit;
                     },
 
 
                     Event::MainEventsCleared => {
                         // update
                         let loop_start = SystemTime::now();
-                        // draw
+                        application.update(dt as f32);
+                        time += dt as f32;
+                        application.present_audio(audio.as_mut());
+
+                        let settings = application.settings();
+                        if settings.window_mode != applied.window_mode {
+                            window.window().set_fullscreen(fullscreen(settings.window_mode, window.window().current_monitor()));
+                        }
+                        if (settings.width, settings.height) != (applied.width, applied.height) && settings.window_mode == WindowMode::Windowed {
+                            window.window().set_inner_size(glutin::dpi::LogicalSize::new(settings.width, settings.height));
+                        }
+                        applied = settings.clone();
+
+                        // draw, through the post-process pass if any of it is on
+                        let post = !applied.post_process_off();
+                        if post {
+                            post_target.bind();
+                        }
+                        shaders.poll();
+                        shaders.use_program(SCENE);
                         gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
 
                         renderer.clear();
                         
-                        gl.uniform_matrix_4_f32_slice(gl.get_uniform_location(program, "projection").as_ref(),
-                            false, &projection_mat.to_cols_array());
+                        shaders.set_mat4(SCENE, "projection", &projection_mat.to_cols_array());
 
-                        application.draw(&mut renderer, window_x/window_y, normalized_cursor_pos);
+                        if !viewport.is_empty() {
+                            application.draw(&mut renderer, viewport.aspect_ratio());
+                        }
 
-                        renderer.present(&gl);
+                        renderer.present(&mut backend);
+
+                        if post {
+                            let fog_colour = applied.theme.colours().background;
+                            shaders.use_program(POST);
+                            shaders.set_i32(POST, "scene", 0);
+                            shaders.set_vec2(POST, "resolution", post_target.width as f32, post_target.height as f32);
+                            // in logical pixels so it looks the same on HiDPI displays
+                            shaders.set_f32(POST, "pixel_size", applied.pixel_size as f32 * viewport.scale_factor as f32);
+                            shaders.set_f32(POST, "fog", applied.fog);
+                            shaders.set_vec3(POST, "fog_colour", fog_colour.x, fog_colour.y, fog_colour.z);
+                            shaders.set_f32(POST, "vignette", applied.vignette);
+                            shaders.set_f32(POST, "time", time);
+                            post_target.present();
+                        }
                         
                         window.swap_buffers().unwrap();
 
 
                         let loop_end = SystemTime::now();
                         let delta = loop_end.duration_since(loop_start).unwrap().as_secs_f64();
-                        let frame_cap = 1.0 / 60.0;
-                        // not sure if this handles vsync ay
-                        if delta < frame_cap {
-                            std::thread::sleep(Duration::from_secs_f64(frame_cap - delta));
-                            dt = frame_cap;
-                        } else {
-                            dt = delta;
+                        // on top of vsync if that's on too
+                        match applied.frame_time() {
+                            Some(frame_time) if delta < frame_time => {
+                                std::thread::sleep(Duration::from_secs_f64(frame_time - delta));
+                                dt = frame_time;
+                            },
+                            _ => dt = delta,
                         }
                     }
 
                     Event::WindowEvent { ref event, .. } => match event {
                         WindowEvent::Resized(physical_size) => {
                             window.resize(*physical_size);
-                            window_x = physical_size.width as f32;
-                            window_y = physical_size.height as f32;
+                            viewport.resize(physical_size.width, physical_size.height);
+                            post_target.resize(physical_size.width, physical_size.height);
                             gl.viewport(0, 0, physical_size.width as i32, physical_size.height as i32);
-                            println!("aspect ratio: {:?}", window_x / window_y);
-                            // level.update_gui(window_x / window_y);
-
                         }
-                        WindowEvent::CloseRequested => {
-                            gl.delete_program(program);
-                            renderer.destroy(&gl);
-                            *control_flow = ControlFlow::Exit
+                        // eg dragged onto a monitor with a different scale, glutin picks the new size
+                        WindowEvent::ScaleFactorChanged {scale_factor, new_inner_size} => {
+                            window.resize(**new_inner_size);
+                            viewport.scale_factor = *scale_factor;
+                            viewport.resize(new_inner_size.width, new_inner_size.height);
+                            post_target.resize(new_inner_size.width, new_inner_size.height);
+                            gl.viewport(0, 0, new_inner_size.width as i32, new_inner_size.height as i32);
                         }
                         WindowEvent::KeyboardInput {
                             input: glutin::event::KeyboardInput { virtual_keycode: Some(virtual_code), state, .. },
@@ -162,79 +195,41 @@ fn main() -> Result<(), Box<dyn Error>> {
                                 glutin::event::ElementState::Released => held_keys.remove(virtual_code),
                             };
 
-                            match (virtual_code, state) {
-                                (glutin::event::VirtualKeyCode::Escape, _) => {
-                                    gl.delete_program(program);
-                                    renderer.destroy(&gl);
-                                    *control_flow = ControlFlow::Exit;
-                                },
-                                (key, glutin::event::ElementState::Pressed) => application.key_press(*key),
-                                /*
-                                (glutin::event::VirtualKeyCode::E, glutin::event::ElementState::Pressed) => {
-                                    level.rotate_forward();
-                                },
-                                (glutin::event::VirtualKeyCode::Q, glutin::event::ElementState::Pressed) => {
-                                    level.rotate_backward();
-                                },
-                                (glutin::event::VirtualKeyCode::N, glutin::event::ElementState::Pressed) => {
-                                    if level_idx > 0 {
-                                        level_idx -= 1;
-                                    }
-                                    level = levels[level_idx].clone();
-                                    level.update_gui(window_x/window_y);
-                                    println!("{}", level.name)
-                                },
-                                (glutin::event::VirtualKeyCode::M, glutin::event::ElementState::Pressed) => {
-                                    if level_idx < levels.len() - 1 {
-                                        level_idx += 1;
-                                    }
-                                    level = levels[level_idx].clone();
-                                    level.update_gui(window_x/window_y);
-                                    println!("{}", level.name)
-                                },
-                                (glutin::event::VirtualKeyCode::P, glutin::event::ElementState::Pressed) => {
-                                    edit = match edit {
-                                        true => {
-                                            println!("edit off");
-                                            false
-                                        },
-                                        false => {
-                                            println!("edit on");
-                                            true
-                                        }
-                                    }
-                                },
-                                */
-                            _ => (),
-                        }},
+                            if *state == glutin::event::ElementState::Pressed {
+                                match bindings.get(Input::Key(*virtual_code)) {
+                                    Some(Action::Quit) => *control_flow = ControlFlow::Exit,
+                                    Some(action) => application.apply(action),
+                                    None => {},
+                                }
+                            }
+                        },
                         WindowEvent::MouseInput {
-                            button: glutin::event::MouseButton::Right,
-                            state: glutin::event::ElementState::Pressed,
+                            button,
+                            state,
                             ..
                         } => {
-                            application.rmb(normalized_cursor_pos);
-                            //game.apply_command(InputCommand::EatGun);
-                        }
-                        WindowEvent::MouseInput {
-                            button: glutin::event::MouseButton::Left,
-                            state,
+                            if let Some(action) = bindings.get(Input::Mouse(*button)) {
+                                match state {
+                                    glutin::event::ElementState::Pressed => application.apply(action),
+                                    glutin::event::ElementState::Released => application.release(action),
+                                }
+                            }
+                        },
+                        WindowEvent::MouseWheel {
+                            delta,
                             ..
                         } => {
-                            if *state == glutin::event::ElementState::Pressed {
-                                lmb = true;
-                                application.lmb(normalized_cursor_pos);
-                            } else {
-                                lmb = false;
+                            let ctrl = held_keys.contains(&glutin::event::VirtualKeyCode::LControl) || held_keys.contains(&glutin::event::VirtualKeyCode::RControl);
+                            let input = Input::from_wheel(*delta, ctrl, application.palette_under_cursor());
+                            if let Some(action) = input.and_then(|input| bindings.get(input)) {
+                                application.apply(action);
                             }
                         },
                         WindowEvent::CursorMoved {
                             position: pos,
                             ..
                         } => {
-                            normalized_cursor_pos = Vec2::new(
-                                // pos.x as f32 / window_x * window_x / window_y, 
-                                pos.x as f32 / window_x, 
-                                pos.y as f32 / window_y);
+                            application.cursor_moved(viewport.to_screen(pos.x, pos.y));
                         },
                         _ => (),
                     },
@@ -0,0 +1,141 @@
+use crate::renderer::*;
+use glow::*;
+use std::rc::Rc;
+
+pub struct GlBackend {
+    gl: Rc<glow::Context>,
+    vbo: NativeBuffer,
+    vao: NativeVertexArray,
+}
+
+impl GlBackend {
+    pub fn new(gl: Rc<glow::Context>) -> GlBackend {
+        unsafe {
+            
+            // We construct a buffer and upload the data
+            let vbo = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+
+            // We now construct a vertex array to describe the format of the input buffer
+            let vao = gl.create_vertex_array().unwrap();
+            gl.bind_vertex_array(Some(vao));
+            
+            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 4*7, 0);
+            gl.enable_vertex_attrib_array(0);
+            gl.vertex_attrib_pointer_f32(1, 4, glow::FLOAT, false, 4*7, 4*3);
+            gl.enable_vertex_attrib_array(1);
+    
+            GlBackend {gl, vao, vbo}
+        }
+    }
+
+    pub fn destroy(&self) {
+        unsafe {
+            self.gl.delete_buffer(self.vbo);
+            self.gl.delete_vertex_array(self.vao);
+        }
+    }
+}
+
+impl RenderBackend for GlBackend {
+    fn submit(&mut self, triangles: &[Triangle3]) {
+        unsafe {
+            let gpu_bytes: &[u8] = core::slice::from_raw_parts(
+                triangles.as_ptr() as *const u8,
+                3 * 4 * 7 * triangles.len(),
+            ); // 3 for points in triangle, 4 for bytes in float, 7 for floats in vertex
+            self.gl.bind_vertex_array(Some(self.vao));
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
+            self.gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, gpu_bytes, glow::DYNAMIC_DRAW);
+            self.gl.draw_arrays(glow::TRIANGLES, 0, triangles.len() as i32 * 3);
+        }
+    }
+}
+
+// the frame gets drawn into this, then onto the window through the post-process
+// shader (src/post.frag) as one triangle over the whole screen
+pub struct PostTarget {
+    gl: Rc<glow::Context>,
+    fbo: NativeFramebuffer,
+    texture: NativeTexture,
+    depth: NativeRenderbuffer,
+    // nothing in it, the post vertex shader makes its own positions
+    vao: NativeVertexArray,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PostTarget {
+    pub fn new(gl: Rc<glow::Context>, width: u32, height: u32) -> PostTarget {
+        unsafe {
+            let fbo = gl.create_framebuffer().unwrap();
+            let texture = gl.create_texture().unwrap();
+            let depth = gl.create_renderbuffer().unwrap();
+            let vao = gl.create_vertex_array().unwrap();
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MIN_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAG_FILTER, glow::NEAREST as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+
+            let mut target = PostTarget {gl, fbo, texture, depth, vao, width: 0, height: 0};
+            target.resize(width, height);
+
+            target.gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+            target.gl.framebuffer_texture_2d(glow::FRAMEBUFFER, glow::COLOR_ATTACHMENT0, glow::TEXTURE_2D, Some(texture), 0);
+            target.gl.framebuffer_renderbuffer(glow::FRAMEBUFFER, glow::DEPTH_ATTACHMENT, glow::RENDERBUFFER, Some(depth));
+            if target.gl.check_framebuffer_status(glow::FRAMEBUFFER) != glow::FRAMEBUFFER_COMPLETE {
+                eprintln!("post-process framebuffer is incomplete");
+            }
+            target.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            target
+        }
+    }
+
+    // sizes the texture and depth buffer to the window, physical pixels
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.tex_image_2d(glow::TEXTURE_2D, 0, glow::RGBA8 as i32, width as i32, height as i32, 0, glow::RGBA, glow::UNSIGNED_BYTE, None);
+            self.gl.bind_renderbuffer(glow::RENDERBUFFER, Some(self.depth));
+            self.gl.renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH_COMPONENT24, width as i32, height as i32);
+        }
+    }
+
+    // draw the frame after this
+    pub fn bind(&self) {
+        unsafe {
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.fbo));
+        }
+    }
+
+    // back to the window and draw the frame onto it with program, which should be
+    // in use with its uniforms set
+    pub fn present(&self) {
+        unsafe {
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            self.gl.disable(glow::DEPTH_TEST);
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
+            self.gl.bind_vertex_array(Some(self.vao));
+            self.gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            self.gl.enable(glow::DEPTH_TEST);
+        }
+    }
+
+    pub fn destroy(&self) {
+        unsafe {
+            self.gl.delete_framebuffer(self.fbo);
+            self.gl.delete_texture(self.texture);
+            self.gl.delete_renderbuffer(self.depth);
+            self.gl.delete_vertex_array(self.vao);
+        }
+    }
+}
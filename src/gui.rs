@@ -0,0 +1,191 @@
+use crate::kmath::*;
+use crate::rect::*;
+use crate::level::GUIElement;
+
+/*
+
+Retained widgets on top of the layout. The (GUIElement, Rect) list from the
+layout only changes when the screen does, so it's kept here along with hover and
+press state, and both drawing and clicking look things up in the same place.
+
+Hit testing goes by z order (GUIElement::z), highest first, ties to whichever
+was laid out later. Elements with no z are decoration and never get hit.
+
+*/
+
+const TOOLTIP_DELAY: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WidgetState {
+    Idle,
+    Hovered,
+    Pressed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Widget {
+    pub elem: GUIElement,
+    pub rect: Rect,
+    pub z: Option<i32>,
+    pub tooltip: Option<String>,
+}
+
+impl Widget {
+    pub fn new(elem: GUIElement, rect: Rect) -> Widget {
+        Widget {elem, rect, z: elem.z(), tooltip: None}
+    }
+
+    pub fn with_tooltip(self, tooltip: Option<String>) -> Widget {
+        Widget {tooltip, ..self}
+    }
+}
+
+pub struct Gui {
+    widgets: Vec<Widget>,
+    cursor: Vec2,
+    hovered: Option<GUIElement>,
+    pressed: Option<GUIElement>,
+    hover_time: f32,
+}
+
+impl Default for Gui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gui {
+    pub fn new() -> Gui {
+        Gui {
+            widgets: Vec::new(),
+            cursor: Vec2::new(-1.0, -1.0),
+            hovered: None,
+            pressed: None,
+            hover_time: 0.0,
+        }
+    }
+
+    // new layout, hover and press carry over for anything still on screen
+    pub fn set_widgets(&mut self, widgets: Vec<Widget>) {
+        self.widgets = widgets;
+        if let Some(pressed) = self.pressed {
+            if self.rect(pressed).is_none() {
+                self.pressed = None;
+            }
+        }
+        self.update_hover();
+    }
+
+    pub fn widgets(&self) -> &[Widget] {
+        &self.widgets
+    }
+
+    pub fn rect(&self, elem: GUIElement) -> Option<Rect> {
+        self.widgets.iter().find(|w| w.elem == elem).map(|w| w.rect)
+    }
+
+    pub fn hit(&self, p: Vec2) -> Option<GUIElement> {
+        let mut best: Option<&Widget> = None;
+        for w in self.widgets.iter() {
+            if let Some(z) = w.z {
+                if w.rect.contains(p) && best.map(|b| z >= b.z.unwrap()).unwrap_or(true) {
+                    best = Some(w);
+                }
+            }
+        }
+        best.map(|w| w.elem)
+    }
+
+    fn update_hover(&mut self) {
+        let hit = self.hit(self.cursor);
+        if hit != self.hovered {
+            self.hovered = hit;
+            self.hover_time = 0.0;
+        }
+    }
+
+    pub fn cursor_moved(&mut self, p: Vec2) {
+        self.cursor = p;
+        self.update_hover();
+    }
+
+    pub fn press(&mut self) -> Option<GUIElement> {
+        self.pressed = self.hovered;
+        self.hover_time = 0.0;
+        self.pressed
+    }
+
+    // what got clicked, if the press and release were on the same widget
+    pub fn release(&mut self) -> Option<GUIElement> {
+        let pressed = self.pressed.take()?;
+        if self.hovered == Some(pressed) {
+            Some(pressed)
+        } else {
+            None
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.hover_time += dt;
+    }
+
+    pub fn state(&self, elem: GUIElement) -> WidgetState {
+        if self.pressed == Some(elem) && self.hovered == Some(elem) {
+            WidgetState::Pressed
+        } else if self.hovered == Some(elem) && self.pressed.is_none() {
+            WidgetState::Hovered
+        } else {
+            WidgetState::Idle
+        }
+    }
+
+    pub fn hovered(&self) -> Option<GUIElement> {
+        self.hovered
+    }
+
+    // text and where to put it, once the cursor has rested on something for a bit
+    pub fn tooltip(&self) -> Option<(&str, Vec2)> {
+        if self.hover_time < TOOLTIP_DELAY || self.pressed.is_some() {
+            return None;
+        }
+        let hovered = self.hovered?;
+        let widget = self.widgets.iter().find(|w| w.elem == hovered)?;
+        widget.tooltip.as_deref().map(|t| (t, self.cursor))
+    }
+}
+
+#[test]
+fn test_gui_hit_and_hover() {
+    let mut gui = Gui::new();
+    gui.set_widgets(vec![
+        Widget::new(GUIElement::Background, Rect::new(0.0, 0.0, 1.0, 1.0)),
+        Widget::new(GUIElement::GameTile(0), Rect::new(0.0, 0.0, 0.5, 0.5)),
+        Widget::new(GUIElement::Button(crate::application::Action::Undo), Rect::new(0.25, 0.25, 0.5, 0.5))
+            .with_tooltip(Some("undo".to_owned())),
+    ]);
+
+    // background is decoration, buttons sit above tiles
+    assert_eq!(gui.hit(Vec2::new(0.9, 0.9)), None);
+    assert_eq!(gui.hit(Vec2::new(0.1, 0.1)), Some(GUIElement::GameTile(0)));
+    let button = GUIElement::Button(crate::application::Action::Undo);
+    assert_eq!(gui.hit(Vec2::new(0.4, 0.4)), Some(button));
+
+    gui.cursor_moved(Vec2::new(0.1, 0.1));
+    assert_eq!(gui.hovered(), Some(GUIElement::GameTile(0)));
+    gui.cursor_moved(Vec2::new(0.4, 0.4));
+    assert_eq!(gui.hovered(), Some(button));
+    assert_eq!(gui.state(button), WidgetState::Hovered);
+    assert_eq!(gui.tooltip(), None);
+    gui.update(1.0);
+    assert_eq!(gui.tooltip(), Some(("undo", Vec2::new(0.4, 0.4))));
+
+    gui.press();
+    assert_eq!(gui.state(button), WidgetState::Pressed);
+    assert_eq!(gui.release(), Some(button));
+
+    // dragging off before letting go doesn't count as a click
+    gui.press();
+    gui.cursor_moved(Vec2::new(0.9, 0.9));
+    assert_eq!(gui.state(button), WidgetState::Idle);
+    assert_eq!(gui.release(), None);
+}
@@ -24,7 +24,7 @@ impl Vec2 {
     pub fn spread(&self, amount: f32) -> Vec2 {
         if amount == 0.0 {return *self};
         let roll = rand::thread_rng().gen_range(-amount..amount);
-        return self.rotate(roll);
+        self.rotate(roll)
     }
 }
 
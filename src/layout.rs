@@ -0,0 +1,215 @@
+use crate::rect::*;
+use crate::level::GUIElement;
+
+/*
+
+Declarative layout: describe the screen as a tree of splits, padding and aspect
+locks, then compute() walks it and hands back the (GUIElement, Rect) list that
+drawing and clicking both use.
+
+Layout::hsplit(vec![
+    (Size::frac(0.15), Layout::elem(GUIElement::Menu).pad(0.02)),
+    (Size::fill(1.0), Layout::elem(GUIElement::GameBoard).aspect(1.0)),
+])
+
+pad and aspect wrap what they're called on, so the last one applies first:
+x.aspect(a).pad(p) pads the rect then fits the aspect inside it.
+
+Sizes along a split are resolved fixed and fractional first, fills share what's
+left by weight. Everything is in the same 0..1 screen units as Rect.
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SizeKind {
+    // absolute, in screen units
+    Fixed(f32),
+    // fraction of the split's length
+    Frac(f32),
+    // weighted share of whatever fixed and frac leave over
+    Fill(f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Size {
+    pub kind: SizeKind,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Size {
+    pub fn fixed(x: f32) -> Size {
+        Size {kind: SizeKind::Fixed(x), min: 0.0, max: f32::INFINITY}
+    }
+    pub fn frac(x: f32) -> Size {
+        Size {kind: SizeKind::Frac(x), min: 0.0, max: f32::INFINITY}
+    }
+    pub fn fill(weight: f32) -> Size {
+        Size {kind: SizeKind::Fill(weight), min: 0.0, max: f32::INFINITY}
+    }
+    pub fn min(self, min: f32) -> Size {
+        Size {min, ..self}
+    }
+    pub fn max(self, max: f32) -> Size {
+        Size {max, ..self}
+    }
+}
+
+// lengths along a split of length total. fills are clamped after sharing, so a
+// clamped fill leaves a gap rather than pushing its space onto the others
+pub fn split_sizes(total: f32, sizes: &[Size]) -> Vec<f32> {
+    let mut lengths: Vec<f32> = sizes.iter().map(|s| match s.kind {
+        SizeKind::Fixed(x) => x.clamp(s.min, s.max),
+        SizeKind::Frac(x) => (x * total).clamp(s.min, s.max),
+        SizeKind::Fill(_) => 0.0,
+    }).collect();
+
+    let used: f32 = lengths.iter().sum();
+    let remaining = (total - used).max(0.0);
+    let weights: f32 = sizes.iter().map(|s| match s.kind {
+        SizeKind::Fill(w) => w,
+        _ => 0.0,
+    }).sum();
+
+    if weights > 0.0 {
+        for (len, s) in lengths.iter_mut().zip(sizes) {
+            if let SizeKind::Fill(w) = s.kind {
+                *len = (remaining * w / weights).clamp(s.min, s.max);
+            }
+        }
+    }
+    lengths
+}
+
+pub enum Layout {
+    Empty,
+    Element(GUIElement),
+    // left to right
+    HSplit(Vec<(Size, Layout)>),
+    // top to bottom
+    VSplit(Vec<(Size, Layout)>),
+    // all children get the same rect, in order, eg an element then its contents
+    Stack(Vec<Layout>),
+    Pad(f32, Box<Layout>),
+    // largest centred child with this w/h, see Rect::child_with_aspect_ratio
+    Aspect(f32, Box<Layout>),
+    // cols x rows equal cells, cells given row by row
+    Grid(usize, usize, Vec<Layout>),
+}
+
+impl Layout {
+    pub fn elem(e: GUIElement) -> Layout {
+        Layout::Element(e)
+    }
+    pub fn hsplit(children: Vec<(Size, Layout)>) -> Layout {
+        Layout::HSplit(children)
+    }
+    pub fn vsplit(children: Vec<(Size, Layout)>) -> Layout {
+        Layout::VSplit(children)
+    }
+    pub fn stack(children: Vec<Layout>) -> Layout {
+        Layout::Stack(children)
+    }
+    pub fn grid(cols: usize, rows: usize, cells: Vec<Layout>) -> Layout {
+        Layout::Grid(cols, rows, cells)
+    }
+    pub fn pad(self, p: f32) -> Layout {
+        Layout::Pad(p, Box::new(self))
+    }
+    pub fn aspect(self, a: f32) -> Layout {
+        Layout::Aspect(a, Box::new(self))
+    }
+
+    pub fn compute(&self, r: Rect) -> Vec<(GUIElement, Rect)> {
+        let mut out = Vec::new();
+        self.emit(r, &mut out);
+        out
+    }
+
+    fn emit(&self, r: Rect, out: &mut Vec<(GUIElement, Rect)>) {
+        match self {
+            Layout::Empty => {},
+            Layout::Element(e) => out.push((*e, r)),
+            Layout::HSplit(children) => {
+                let sizes: Vec<Size> = children.iter().map(|(s, _)| *s).collect();
+                let mut x = r.x;
+                for (len, (_, child)) in split_sizes(r.w, &sizes).into_iter().zip(children) {
+                    child.emit(Rect::new(x, r.y, len, r.h), out);
+                    x += len;
+                }
+            },
+            Layout::VSplit(children) => {
+                let sizes: Vec<Size> = children.iter().map(|(s, _)| *s).collect();
+                let mut y = r.y;
+                for (len, (_, child)) in split_sizes(r.h, &sizes).into_iter().zip(children) {
+                    child.emit(Rect::new(r.x, y, r.w, len), out);
+                    y += len;
+                }
+            },
+            Layout::Stack(children) => {
+                for child in children {
+                    child.emit(r, out);
+                }
+            },
+            Layout::Pad(p, child) => child.emit(r.dilate(-p), out),
+            Layout::Aspect(a, child) => child.emit(r.child_with_aspect_ratio(*a), out),
+            Layout::Grid(cols, rows, cells) => {
+                // column by column, which is the order the board has always been drawn in
+                for i in 0..*cols {
+                    for j in 0..*rows {
+                        if let Some(cell) = cells.get(j * cols + i) {
+                            let fw = 1.0 / *cols as f32;
+                            let fh = 1.0 / *rows as f32;
+                            cell.emit(r.child(i as f32 * fw, j as f32 * fh, fw, fh), out);
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_close(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b) {
+        assert!((x - y).abs() < 0.0001, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn test_split_sizes() {
+    let sizes = [Size::fixed(0.2), Size::fill(1.0), Size::frac(0.25), Size::fill(3.0)];
+    let lengths = split_sizes(2.0, &sizes);
+    assert_close(&lengths, &[0.2, 0.325, 0.5, 0.975]);
+
+    let clamped = split_sizes(1.0, &[Size::frac(0.5).max(0.1), Size::fill(1.0).min(0.95)]);
+    assert_close(&clamped, &[0.1, 0.95]);
+}
+
+#[test]
+fn test_layout_tree() {
+    let layout = Layout::stack(vec![
+        Layout::elem(GUIElement::Background),
+        Layout::hsplit(vec![
+            (Size::frac(0.25), Layout::elem(GUIElement::Menu).pad(0.05)),
+            (Size::fill(1.0), Layout::grid(2, 1, vec![
+                Layout::elem(GUIElement::GameTile(0)),
+                Layout::elem(GUIElement::GameTile(1)),
+            ]).aspect(1.5)),
+        ]),
+    ]);
+    let gui = layout.compute(Rect::new(0.0, 0.0, 1.0, 1.0));
+
+    let elems: Vec<GUIElement> = gui.iter().map(|(e, _)| *e).collect();
+    assert_eq!(elems, vec![GUIElement::Background, GUIElement::Menu, GUIElement::GameTile(0), GUIElement::GameTile(1)]);
+    let expected = [
+        Rect::new(0.0, 0.0, 1.0, 1.0),
+        Rect::new(0.05, 0.05, 0.15, 0.9),
+        Rect::new(0.25, 0.25, 0.375, 0.5),
+        Rect::new(0.625, 0.25, 0.375, 0.5),
+    ];
+    for ((_, r), e) in gui.iter().zip(expected.iter()) {
+        assert_close(&[r.x, r.y, r.w, r.h], &[e.x, e.y, e.w, e.h]);
+    }
+}
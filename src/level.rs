@@ -2,10 +2,12 @@ use crate::kmath::*;
 use crate::renderer::*;
 use crate::rect::*;
 use crate::application::*;
+use crate::anim::*;
+use crate::layout::*;
 use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::prelude::*;
-use std::fs;
 
 pub type Tile = [Vec3; 4];
 pub trait TileRotate {
@@ -22,6 +24,84 @@ impl TileRotate for Tile {
     }
 }
 
+// distinct orientations of a tile, starting with the one given
+pub fn tile_rotations(tile: Tile) -> Vec<Tile> {
+    let mut rotations = vec![tile];
+    let mut t = tile.rotate_cw();
+    while t != tile {
+        rotations.push(t);
+        t = t.rotate_cw();
+    }
+    rotations
+}
+
+pub fn mirror_h(tile: Tile) -> Tile {
+    [tile[0], tile[3], tile[2], tile[1]]
+}
+
+pub fn mirror_v(tile: Tile) -> Tile {
+    [tile[2], tile[1], tile[0], tile[3]]
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Symmetry {
+    #[default]
+    None,
+    // left to right
+    MirrorH,
+    // top to bottom
+    MirrorV,
+    Rot2,
+    // square boards only, otherwise acts as Rot2
+    Rot4,
+}
+
+impl Symmetry {
+    pub fn next(&self) -> Symmetry {
+        match self {
+            Symmetry::None => Symmetry::MirrorH,
+            Symmetry::MirrorH => Symmetry::MirrorV,
+            Symmetry::MirrorV => Symmetry::Rot2,
+            Symmetry::Rot2 => Symmetry::Rot4,
+            Symmetry::Rot4 => Symmetry::None,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Symmetry> {
+        match s {
+            "none" => Some(Symmetry::None),
+            "mirrorh" => Some(Symmetry::MirrorH),
+            "mirrorv" => Some(Symmetry::MirrorV),
+            "rot2" => Some(Symmetry::Rot2),
+            "rot4" => Some(Symmetry::Rot4),
+            _ => None,
+        }
+    }
+
+    // where else a tile placed at idx goes and how it's transformed, not including idx itself
+    pub fn images(&self, w: usize, h: usize, idx: usize, tile: Tile) -> Vec<(usize, Tile)> {
+        let (x, y) = (idx % w, idx / w);
+        let mut images = match self {
+            Symmetry::None => vec![],
+            Symmetry::MirrorH => vec![((w - 1 - x, y), mirror_h(tile))],
+            Symmetry::MirrorV => vec![((x, h - 1 - y), mirror_v(tile))],
+            Symmetry::Rot4 if w == h => vec![
+                ((w - 1 - y, x), tile.rotate_cw()),
+                ((w - 1 - x, h - 1 - y), tile.rotate_cw().rotate_cw()),
+                ((y, h - 1 - x), tile.rotate_ccw()),
+            ],
+            Symmetry::Rot2 | Symmetry::Rot4 => vec![((w - 1 - x, h - 1 - y), tile.rotate_cw().rotate_cw())],
+        }.into_iter()
+            .map(|((x, y), t)| (y*w + x, t))
+            .filter(|(i, _)| *i != idx)
+            .collect::<Vec<_>>();
+        // first copy wins, keeping the order they go round in
+        let mut seen = HashSet::new();
+        images.retain(|(i, _)| seen.insert(*i));
+        images
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct LevelData {
     pub name: String,
@@ -29,6 +109,14 @@ pub struct LevelData {
     pub h: usize,
     pub tile_choices: Vec<Tile>,
     pub fixed_tiles: Vec<Option<Tile>>,
+    #[serde(default)]
+    pub symmetry: Symmetry,
+    // players cant turn the symmetry off
+    #[serde(default)]
+    pub require_symmetry: bool,
+    // levels are grouped by pack on the level select screen
+    #[serde(default)]
+    pub pack: String,
 }
 
 
@@ -44,6 +132,9 @@ impl LevelData {
             h,
             tile_choices,
             fixed_tiles: vec![None; w*h],
+            symmetry: Symmetry::None,
+            require_symmetry: false,
+            pack: String::new(),
         }
     }
 
@@ -62,14 +153,35 @@ impl LevelData {
 
 
 
+// (solution, fixed tiles) before a change, for undo
+pub type Snapshot = (Vec<Option<Tile>>, Vec<Option<Tile>>);
+
 pub struct Level {
     // pub data: LevelData,
     pub current_solution: Vec<Option<Tile>>,
     pub selected_tile: Tile,
     pub selected_tile_idx: i32,
+    pub history: Vec<Snapshot>,
+    pub symmetry: Symmetry,
+    // edits made in edit mode that haven't been saved
+    pub unsaved: bool,
 }
 
-#[derive(Clone, Copy)]
+impl Level {
+    pub fn checkpoint(&mut self, ld: &LevelData) {
+        self.history.push((self.current_solution.clone(), ld.fixed_tiles.clone()));
+    }
+
+    // every cell filled and every edge matching
+    pub fn is_solved(&self, ld: &LevelData) -> bool {
+        self.current_solution.iter().enumerate().all(|(i, t)| match t {
+            Some(t) => fits(ld.w, ld.h, &self.current_solution, *t, i),
+            None => false,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GUIElement {
     Background,
     Menu,
@@ -80,6 +192,23 @@ pub enum GUIElement {
     GridLineH,
     GridLineV,
     SelectionIndicator,
+    StatusBar,
+    MessageBar,
+    Button(Action),
+    PackHeader(usize),
+    LevelThumb(usize),
+    SettingRow(usize),
+}
+
+impl GUIElement {
+    // stacking for hit testing, higher wins. None is decoration that can't be hit
+    pub fn z(&self) -> Option<i32> {
+        match self {
+            GUIElement::GameTile(_) | GUIElement::MenuTile(_) | GUIElement::LevelThumb(_) => Some(1),
+            GUIElement::Button(_) => Some(2),
+            _ => None,
+        }
+    }
 }
 
 
@@ -176,26 +305,41 @@ impl Level {
 }
 */
 
-pub fn draw_level(renderer: &mut Renderer, cursor_pos: Vec2, selected_tile: Tile, tile_choices: &[Tile], tiles: &[Option<Tile>], fixed: &[bool], gui_elements: &[(GUIElement, Rect)]) {
-    let empty_colour = Vec4::new(0.2, 0.2, 0.2, 1.0);
-    let fixed_t = 0.4;
+#[allow(clippy::too_many_arguments)]
+pub fn draw_level(renderer: &mut Renderer, cursor_pos: Vec2, selected_tile: Tile, tile_choices: &[Tile], tiles: &[Option<Tile>], fixed: &[bool], legal: Option<&[Option<LegalCell>]>, anims: Option<&Animations>, gui_elements: &[(GUIElement, Rect)]) {
+    let board_rect = gui_elements.iter().find(|(e, _)| *e == GUIElement::GameBoard).map(|(_, r)| *r);
 
     for (elem_type, rect) in gui_elements.iter() {
         match elem_type {
             GUIElement::GameTile(i) => {
-                if let Some(tile) = tiles[*i] {
-                    if fixed[*i] {
-                        renderer.draw_tile_reverse_bevel(*rect, tile[0], tile[1], tile[2], tile[3], 10.0, 1.0);
-                    } else {
-                        renderer.draw_tile(*rect, tile[0], tile[1], tile[2], tile[3], 10.0, 1.0);
+                match anims.and_then(|a| a.cell(*i)) {
+                    Some(anim) => draw_animated_board_tile(renderer, *rect, tiles[*i], fixed[*i], anim),
+                    None => draw_board_tile(renderer, *rect, tiles[*i], fixed[*i]),
+                }
+
+                if let Some(Some(cell)) = legal.map(|x| x[*i]) {
+                    draw_legal_cell(renderer, *rect, cell);
+                }
+
+                if let (Some(front), Some(board)) = (anims.and_then(|a| a.sweep()), board_rect) {
+                    let c = rect.centroid();
+                    let d = ((c.x - board.x) / board.w + (c.y - board.y) / board.h) / 2.0;
+                    let a = (1.0 - (d - front).abs() * 5.0).max(0.0) * 0.6;
+                    if a > 0.0 {
+                        renderer.draw_rect(*rect, Vec4::new(1.0, 1.0, 1.0, a), 13.0);
                     }
-                } else {
-                    renderer.draw_rect(*rect, empty_colour, 10.0);
                 }
 
                 if let Some(idx) = GetClickedGameTile(cursor_pos, gui_elements) {
                     if idx == *i && !fixed[*i] {
-                        renderer.draw_tile(*rect, selected_tile[0], selected_tile[1], selected_tile[2], selected_tile[3], 15.0, 0.5);
+                        let angle = anims.map(|a| a.ghost_angle()).unwrap_or(0.0);
+                        let shake = anims.map(|a| a.shake_offset(*i)).unwrap_or(0.0);
+                        if angle == 0.0 && shake == 0.0 {
+                            renderer.draw_tile(*rect, selected_tile[0], selected_tile[1], selected_tile[2], selected_tile[3], 15.0, 0.5);
+                        } else {
+                            let r = rect.translate(Vec2::new(shake * rect.w, 0.0));
+                            renderer.draw_tile_rotated(r, selected_tile[0], selected_tile[1], selected_tile[2], selected_tile[3], angle, 15.0, 0.5);
+                        }
                     }
                 }
             },
@@ -214,15 +358,51 @@ pub fn draw_level(renderer: &mut Renderer, cursor_pos: Vec2, selected_tile: Tile
     }
 }
 
-pub fn accept(w: usize, h: usize, tiles: &Vec<Option<Tile>>, place_tile: Tile, place_idx: usize) -> bool {
+fn draw_animated_board_tile(renderer: &mut Renderer, rect: Rect, tile: Option<Tile>, fixed: bool, anim: CellAnim) {
+    let (tile, scale) = match (anim.kind, tile) {
+        (CellAnimKind::Place, Some(tile)) => (tile, ease_out_back(anim.tween.t())),
+        (CellAnimKind::Remove(old), None) => (old, 1.0 - ease_out_cubic(anim.tween.t())),
+        _ => return draw_board_tile(renderer, rect, tile, fixed),
+    };
+
+    draw_board_tile(renderer, rect, None, false);
+    let c = rect.centroid();
+    let r = Rect::new_centered(c.x, c.y, rect.w * scale, rect.h * scale);
+    // just above the empty cell
+    if fixed {
+        renderer.draw_tile_reverse_bevel(r, tile[0], tile[1], tile[2], tile[3], 10.1, 1.0);
+    } else {
+        renderer.draw_tile(r, tile[0], tile[1], tile[2], tile[3], 10.1, 1.0);
+    }
+}
+
+pub fn draw_board_tile(renderer: &mut Renderer, rect: Rect, tile: Option<Tile>, fixed: bool) {
+    let empty_colour = Vec4::new(0.2, 0.2, 0.2, 1.0);
+
+    if let Some(tile) = tile {
+        if fixed {
+            renderer.draw_tile_reverse_bevel(rect, tile[0], tile[1], tile[2], tile[3], 10.0, 1.0);
+        } else {
+            renderer.draw_tile(rect, tile[0], tile[1], tile[2], tile[3], 10.0, 1.0);
+        }
+    } else {
+        renderer.draw_rect(rect, empty_colour, 10.0);
+    }
+}
+
+// for checking lots of placements eg every frame, reject_reason says why one doesn't fit
+pub fn fits(w: usize, h: usize, tiles: &[Option<Tile>], place_tile: Tile, place_idx: usize) -> bool {
+    reject_reason(w, h, tiles, place_tile, place_idx).is_none()
+}
+
+pub fn reject_reason(w: usize, h: usize, tiles: &[Option<Tile>], place_tile: Tile, place_idx: usize) -> Option<&'static str> {
     let x = place_idx % w;
     let y = place_idx / w;
 
     if x != 0 {
         if let Some(neigh) = tiles[place_idx - 1] {
             if neigh[1] != place_tile[3] {
-                println!("reject left edge neighbour");
-                return false;
+                return Some("left edge neighbour");
             }
         }
     }
@@ -230,8 +410,7 @@ pub fn accept(w: usize, h: usize, tiles: &Vec<Option<Tile>>, place_tile: Tile, p
     if x != w - 1 {
         if let Some(neigh) = tiles[place_idx + 1] {
             if neigh[3] != place_tile[1] {
-                println!("reject right edge neighbour");
-                return false;
+                return Some("right edge neighbour");
             }
         }
     }
@@ -239,8 +418,7 @@ pub fn accept(w: usize, h: usize, tiles: &Vec<Option<Tile>>, place_tile: Tile, p
     if y != h - 1 {
         if let Some(neigh) = tiles[place_idx + w] {
             if neigh[0] != place_tile[2] {
-                println!("reject bottom edge neighbour");
-                return false;
+                return Some("bottom edge neighbour");
             }
         }
     }
@@ -248,54 +426,359 @@ pub fn accept(w: usize, h: usize, tiles: &Vec<Option<Tile>>, place_tile: Tile, p
     if y != 0 {
         if let Some(neigh) = tiles[place_idx - w] {
             if neigh[2] != place_tile[0] {
-                println!("reject top edge neighbour");
-                return false;
+                return Some("top edge neighbour");
             }
         }
     }
-    true
+    None
 }
 
-pub fn calculate_gui(n_tiles: usize, w: usize, h: usize, aspect_ratio: f32, selected_tile: i32) -> Vec<(GUIElement, Rect)> {
-    let mut vec = Vec::new();
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LegalCell {
+    // selected tile fits here in some rotation
+    pub selected_fits: bool,
+    // how many palette tiles fit here in some rotation
+    pub palette_fits: usize,
+}
 
-    let screen_rect = Rect::new(0.0, 0.0, 1.0, 1.0);
-    vec.push((GUIElement::Background, screen_rect));
+// None for cells that already have a tile
+pub fn legal_moves(w: usize, h: usize, tiles: &[Option<Tile>], selected_tile: Tile, tile_choices: &[Tile]) -> Vec<Option<LegalCell>> {
+    let any_rotation_fits = |tile: Tile, idx: usize| tile_rotations(tile).into_iter().any(|t| fits(w, h, tiles, t, idx));
 
-    let menu_pane_w = 0.15;
-    let menu_rect = screen_rect.child(0.0, 0.0, menu_pane_w, 1.0).dilate(-0.02);
-    vec.push((GUIElement::Menu, menu_rect));
-    
-    let game_rect = screen_rect.child(menu_pane_w, 0.0, 1.0 - menu_pane_w, 1.0);
-    let board_rect = game_rect.dilate(-0.11).child_with_aspect_ratio(w as f32 / h as f32 / aspect_ratio);
-    vec.push((GUIElement::GameBoard, board_rect));
-    
-    let menu_tile_size = (1.0 / n_tiles as f32).min(0.15);
-    for i in 0..n_tiles {
-        let sel_rect = menu_rect.child(0.0, i as f32 * menu_tile_size, 1.0, menu_tile_size);
-        let choice_rect = sel_rect.dilate(-0.002).child_with_aspect_ratio(1.0 / aspect_ratio);
-        vec.push((GUIElement::MenuTile(i), choice_rect));
-        if i == selected_tile as usize {
-            vec.push((GUIElement::SelectionIndicator, sel_rect));
+    (0..w*h).map(|idx| {
+        if tiles[idx].is_some() {
+            return None;
         }
+        Some(LegalCell {
+            selected_fits: any_rotation_fits(selected_tile, idx),
+            palette_fits: tile_choices.iter().filter(|t| any_rotation_fits(**t, idx)).count(),
+        })
+    }).collect()
+}
+
+fn draw_legal_cell(renderer: &mut Renderer, rect: Rect, cell: LegalCell) {
+    if cell.selected_fits {
+        renderer.draw_rect(rect, Vec4::new(0.3, 1.0, 0.3, 0.25), 12.0);
     }
-    
-    for i in 0..w {
-        for j in 0..h {
-            let tile_rect = board_rect.child(i as f32 / w as f32, j as f32 / h as f32, 1.0 / w as f32, 1.0 / h as f32); //.dilate(-0.002);
-            vec.push((GUIElement::GameTile(j*w + i), tile_rect));
+
+    // count as little squares along the bottom, a red one if nothing fits
+    let size = rect.w.min(rect.h) * 0.1;
+    let gap = size * 0.5;
+    if cell.palette_fits == 0 {
+        renderer.draw_rect(Rect::new(rect.x + gap, rect.bot() - gap - size, size, size), Vec4::new(1.0, 0.0, 0.0, 1.0), 12.5);
+    }
+    for i in 0..cell.palette_fits {
+        let x = rect.x + gap + i as f32 * (size + gap);
+        if x + size > rect.right() {
+            break;
+        }
+        renderer.draw_rect(Rect::new(x, rect.bot() - gap - size, size, size), Vec4::new(1.0, 1.0, 1.0, 1.0), 12.5);
+    }
+}
+
+// menu pane as a fraction of the screen, and its padding
+const MENU_PANE_W: f32 = 0.15;
+const MENU_PAD: f32 = 0.02;
+// palette cell heights, as a fraction of the menu height
+const MAX_MENU_TILE: f32 = 0.15;
+const MIN_MENU_TILE: f32 = 0.06;
+const MAX_MENU_COLS: usize = 3;
+
+// buttons along the bottom right, each just applies its action
+pub const TOOLBAR: [Action; 6] = [Action::Settings, Action::LevelSelect, Action::Undo, Action::ToggleHints, Action::ToggleAutoRotate, Action::CycleSymmetry];
+
+/*
+
+How the palette fits in the menu pane. Wraps into as many columns as gives the
+biggest tiles, and once even that would make them too small it scrolls by rows
+instead. Tiles are shown grouped by their most common edge colour.
+
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    // display slot -> index into tile_choices
+    pub order: Vec<usize>,
+    pub cols: usize,
+    pub rows: usize,
+    pub visible_rows: usize,
+    pub tile_size: f32,
+}
+
+impl Palette {
+    pub fn new(tiles: &[Tile], aspect_ratio: f32) -> Palette {
+        let n = tiles.len().max(1);
+        let menu_w = MENU_PANE_W - 2.0 * MENU_PAD;
+        let menu_h = 1.0 - 2.0 * MENU_PAD;
+        // tallest a cell can be and still hold a square tile across its width
+        let width_limit = |cols: usize| menu_w * aspect_ratio / (cols as f32 * menu_h);
+        let fit_size = |cols: usize| width_limit(cols).min(1.0 / n.div_ceil(cols) as f32).min(MAX_MENU_TILE);
+
+        let mut cols = 1;
+        for c in 2..=MAX_MENU_COLS {
+            if fit_size(c) > fit_size(cols) {
+                cols = c;
+            }
+        }
+        let mut tile_size = fit_size(cols);
+
+        if tile_size < MIN_MENU_TILE {
+            cols = (1..=MAX_MENU_COLS).rev().find(|c| width_limit(*c) >= MIN_MENU_TILE).unwrap_or(1);
+            tile_size = width_limit(cols).clamp(MIN_MENU_TILE, MAX_MENU_TILE);
+        }
+
+        let rows = n.div_ceil(cols);
+        let visible_rows = ((1.0 / tile_size + 0.001) as usize).clamp(1, rows);
+        Palette {order: palette_order(tiles), cols, rows, visible_rows, tile_size}
+    }
+
+    pub fn max_scroll(&self) -> usize {
+        self.rows - self.visible_rows
+    }
+
+    pub fn row_of(&self, tile_idx: usize) -> usize {
+        self.order.iter().position(|i| *i == tile_idx).unwrap_or(0) / self.cols
+    }
+
+    // smallest change to scroll that has tile_idx on screen
+    pub fn reveal(&self, scroll: usize, tile_idx: usize) -> usize {
+        let row = self.row_of(tile_idx);
+        if row < scroll {
+            row
+        } else if row >= scroll + self.visible_rows {
+            row + 1 - self.visible_rows
+        } else {
+            scroll
+        }.min(self.max_scroll())
+    }
+}
+
+fn dominant_colour(tile: Tile) -> Vec3 {
+    let count = |c: Vec3| tile.iter().filter(|x| **x == c).count();
+    let mut best = tile[0];
+    for c in tile.iter() {
+        if count(*c) > count(best) {
+            best = *c;
+        }
+    }
+    best
+}
+
+// tile indices grouped by dominant colour, groups in order of first appearance
+pub fn palette_order(tiles: &[Tile]) -> Vec<usize> {
+    let mut groups: Vec<Vec3> = Vec::new();
+    let mut keyed: Vec<(usize, usize)> = Vec::new();
+    for (i, tile) in tiles.iter().enumerate() {
+        let colour = dominant_colour(*tile);
+        let group = match groups.iter().position(|c| *c == colour) {
+            Some(g) => g,
+            None => {
+                groups.push(colour);
+                groups.len() - 1
+            },
+        };
+        keyed.push((group, i));
+    }
+    keyed.sort();
+    keyed.into_iter().map(|(_, i)| i).collect()
+}
+
+pub fn calculate_gui(palette: &Palette, scroll: usize, w: usize, h: usize, aspect_ratio: f32, selected_tile: i32) -> Vec<(GUIElement, Rect)> {
+    gui_layout(palette, scroll, w, h, aspect_ratio, selected_tile).compute(Rect::new(0.0, 0.0, 1.0, 1.0))
+}
+
+pub fn gui_layout(palette: &Palette, scroll: usize, w: usize, h: usize, aspect_ratio: f32, selected_tile: i32) -> Layout {
+    let visible = scroll..(scroll + palette.visible_rows).min(palette.rows);
+    let menu_rows = visible.map(|row| {
+        let cells = (0..palette.cols).map(|col| {
+            let cell = match palette.order.get(row * palette.cols + col) {
+                Some(&i) => {
+                    let mut cell = vec![Layout::elem(GUIElement::MenuTile(i)).aspect(1.0 / aspect_ratio).pad(0.002)];
+                    if i == selected_tile as usize {
+                        cell.push(Layout::elem(GUIElement::SelectionIndicator));
+                    }
+                    Layout::stack(cell)
+                },
+                None => Layout::Empty,
+            };
+            (Size::fill(1.0), cell)
+        }).collect();
+        (Size::frac(palette.tile_size), Layout::hsplit(cells))
+    }).collect();
+
+    let board_tiles = (0..w*h).map(|i| Layout::elem(GUIElement::GameTile(i))).collect();
+    let board = Layout::stack(vec![
+        Layout::elem(GUIElement::GameBoard),
+        Layout::grid(w, h, board_tiles),
+    ]);
+
+    Layout::stack(vec![
+        Layout::elem(GUIElement::Background),
+        Layout::hsplit(vec![
+            (Size::frac(MENU_PANE_W), Layout::stack(vec![
+                Layout::elem(GUIElement::Menu),
+                Layout::vsplit(menu_rows),
+            ]).pad(MENU_PAD)),
+            (Size::fill(1.0), Layout::stack(vec![
+                board.aspect(w as f32 / h as f32 / aspect_ratio).pad(0.11),
+                Layout::vsplit(vec![
+                    (Size::frac(0.11), Layout::elem(GUIElement::StatusBar).pad(0.03)),
+                    (Size::fill(1.0), Layout::Empty),
+                    (Size::frac(0.11), Layout::hsplit(vec![
+                        (Size::fill(1.0), Layout::elem(GUIElement::MessageBar).pad(0.03)),
+                        (Size::frac(0.5), Layout::hsplit(TOOLBAR.iter().map(|a| {
+                            (Size::fill(1.0), Layout::elem(GUIElement::Button(*a)).pad(0.005))
+                        }).collect()).pad(0.025)),
+                    ])),
+                ]),
+            ])),
+        ]),
+    ])
+}
+
+// pack name and the levels in it, packs in the order they first turn up
+pub fn level_packs(level_datas: &[LevelData]) -> Vec<(String, Vec<usize>)> {
+    let mut packs: Vec<(String, Vec<usize>)> = Vec::new();
+    for (i, ld) in level_datas.iter().enumerate() {
+        let name = if ld.pack.is_empty() { "levels" } else { &ld.pack };
+        match packs.iter_mut().find(|(p, _)| p == name) {
+            Some((_, levels)) => levels.push(i),
+            None => packs.push((name.to_owned(), vec![i])),
+        }
+    }
+    packs
+}
+
+const LEVEL_SELECT_COLS: usize = 5;
+
+// buttons along each row of the settings screen, rows as in Application::setting_text
+pub const SETTINGS_ROWS: [&[Action]; 12] = [
+    &[Action::CycleWindowMode],
+    &[Action::CycleResolution],
+    &[Action::ToggleVsync],
+    &[Action::CycleFrameCap],
+    &[Action::VolumeDown, Action::VolumeUp, Action::ToggleMute],
+    &[Action::CycleTheme],
+    &[Action::CycleColourVision],
+    &[Action::ToggleEdgeSymbols],
+    &[Action::CycleTileStyle],
+    &[Action::ToggleFog],
+    &[Action::ToggleVignette],
+    &[Action::CyclePixelSize],
+];
+
+pub fn settings_gui() -> Vec<(GUIElement, Rect)> {
+    let mut rows: Vec<(Size, Layout)> = SETTINGS_ROWS.iter().enumerate().map(|(i, actions)| {
+        let buttons = actions.iter().map(|a| (Size::fill(1.0), Layout::elem(GUIElement::Button(*a)).pad(0.01))).collect();
+        (Size::frac(0.075), Layout::hsplit(vec![
+            (Size::fill(1.0), Layout::elem(GUIElement::SettingRow(i))),
+            (Size::frac(0.4), Layout::hsplit(buttons)),
+        ]))
+    }).collect();
+    rows.push((Size::fill(1.0), Layout::Empty));
+
+    Layout::stack(vec![
+        Layout::elem(GUIElement::Background),
+        Layout::vsplit(vec![
+            (Size::frac(0.11), Layout::hsplit(vec![
+                (Size::fill(1.0), Layout::elem(GUIElement::StatusBar).pad(0.03)),
+                (Size::frac(0.15), Layout::elem(GUIElement::Button(Action::Settings)).pad(0.03)),
+            ])),
+            (Size::fill(1.0), Layout::vsplit(rows).pad(0.03)),
+        ]),
+    ]).compute(Rect::new(0.0, 0.0, 1.0, 1.0))
+}
+
+pub fn level_select_gui(level_datas: &[LevelData]) -> Vec<(GUIElement, Rect)> {
+    level_select_layout(&level_packs(level_datas)).compute(Rect::new(0.0, 0.0, 1.0, 1.0))
+}
+
+pub fn level_select_layout(packs: &[(String, Vec<usize>)]) -> Layout {
+    let rows: usize = packs.iter().map(|(_, levels)| levels.len().div_ceil(LEVEL_SELECT_COLS)).sum();
+    // headers are half a row
+    let row_size = (1.0 / (rows as f32 + 0.5 * packs.len() as f32)).min(0.3);
+
+    let mut sections = Vec::new();
+    for (p, (_, levels)) in packs.iter().enumerate() {
+        sections.push((Size::frac(row_size * 0.5), Layout::elem(GUIElement::PackHeader(p))));
+        for row in levels.chunks(LEVEL_SELECT_COLS) {
+            let cells = (0..LEVEL_SELECT_COLS).map(|col| {
+                let cell = match row.get(col) {
+                    Some(&i) => Layout::elem(GUIElement::LevelThumb(i)).pad(0.01),
+                    None => Layout::Empty,
+                };
+                (Size::fill(1.0), cell)
+            }).collect();
+            sections.push((Size::frac(row_size), Layout::hsplit(cells)));
+        }
+    }
+
+    Layout::stack(vec![
+        Layout::elem(GUIElement::Background),
+        Layout::vsplit(vec![
+            (Size::frac(0.11), Layout::hsplit(vec![
+                (Size::fill(1.0), Layout::elem(GUIElement::StatusBar).pad(0.03)),
+                (Size::frac(0.15), Layout::elem(GUIElement::Button(Action::LevelSelect)).pad(0.03)),
+            ])),
+            (Size::fill(1.0), Layout::vsplit(sections).pad(0.03)),
+        ]),
+    ])
+}
+
+// a level at a glance: its board as it stands, palette underneath, then the name
+#[allow(clippy::too_many_arguments)]
+pub fn draw_level_thumbnail(renderer: &mut Renderer, r: Rect, ld: &LevelData, solution: &[Option<Tile>], solved: bool, in_progress: bool, highlight: bool, aspect_ratio: f32) {
+    let border = if solved {
+        Vec4::new(0.2, 0.8, 0.2, 1.0)
+    } else if in_progress {
+        Vec4::new(0.9, 0.8, 0.2, 1.0)
+    } else {
+        Vec4::new(0.2, 0.2, 0.2, 1.0)
+    };
+    let panel = if highlight { Vec4::new(0.35, 0.35, 0.35, 1.0) } else { Vec4::new(0.25, 0.25, 0.25, 1.0) };
+    renderer.draw_rect(r, border, 5.0);
+    let inner = r.dilate(-0.004);
+    renderer.draw_rect(inner, panel, 6.0);
+
+    let board_rect = inner.child(0.0, 0.0, 1.0, 0.66).dilate(-0.008).child_with_aspect_ratio(ld.w as f32 / ld.h as f32 / aspect_ratio);
+    let empty_colour = Vec4::new(0.2, 0.2, 0.2, 1.0);
+    for i in 0..ld.w {
+        for j in 0..ld.h {
+            let cell = board_rect.child(i as f32 / ld.w as f32, j as f32 / ld.h as f32, 1.0 / ld.w as f32, 1.0 / ld.h as f32);
+            match solution[j*ld.w + i] {
+                Some(t) => renderer.draw_tile_no_bevel(cell, t[0], t[1], t[2], t[3], 7.0, 1.0),
+                None => renderer.draw_rect(cell, empty_colour, 7.0),
+            }
         }
     }
 
-    vec
+    let palette_rect = inner.child(0.0, 0.68, 1.0, 0.14);
+    let size = palette_rect.h;
+    let step = size / aspect_ratio * 1.2;
+    let total = step * ld.tile_choices.len() as f32;
+    let mut x = palette_rect.x + ((palette_rect.w - total) / 2.0).max(0.0);
+    for t in ld.tile_choices.iter() {
+        if x + size / aspect_ratio > palette_rect.right() {
+            break;
+        }
+        renderer.draw_tile_no_bevel(Rect::new(x, palette_rect.y, size / aspect_ratio, size), t[0], t[1], t[2], t[3], 7.0, 1.0);
+        x += step;
+    }
+
+    let name_rect = inner.child(0.05, 0.84, 0.9, 0.14);
+    renderer.draw_text_in(name_rect, &ld.name, Align::Centre, aspect_ratio, Vec4::new(1.0, 1.0, 1.0, 1.0), 8.0);
+
+    if solved || in_progress {
+        let size = inner.h * 0.12;
+        renderer.draw_rect(Rect::new(inner.right() - size / aspect_ratio * 1.5, inner.y + size * 0.5, size / aspect_ratio, size), border, 8.0);
+    }
 }
 
-pub fn GetClickedGameTile(p: Vec2, gui: &[(GUIElement, Rect)]) -> Option<(usize)> {
+#[allow(non_snake_case)]
+pub fn GetClickedGameTile(p: Vec2, gui: &[(GUIElement, Rect)]) -> Option<usize> {
     for (element, rect) in gui {
         if rect.contains(p) {
-            match element {
-                GUIElement::GameTile(idx) => {return Some(*idx)},
-                _ => {},
+            if let GUIElement::GameTile(idx) = element {
+                return Some(*idx);
             }
         }
     }
@@ -304,16 +787,184 @@ pub fn GetClickedGameTile(p: Vec2, gui: &[(GUIElement, Rect)]) -> Option<(usize)
 
 }
 
-pub fn GetClickedMenuTile(p: Vec2, gui: &Vec<(GUIElement, Rect)>) -> Option<(usize)> {
+#[allow(non_snake_case)]
+pub fn GetClickedMenuTile(p: Vec2, gui: &[(GUIElement, Rect)]) -> Option<usize> {
     for (element, rect) in gui {
         if rect.contains(p) {
-            match element {
-                GUIElement::MenuTile(idx) => {return Some(*idx)},
-                _ => {},
+            if let GUIElement::MenuTile(idx) = element {
+                return Some(*idx);
             }
         }
     }
 
     None
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_tile_rotations() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    assert_eq!(tile_rotations([r, r, r, r]), vec![[r, r, r, r]]);
+    assert_eq!(tile_rotations([r, g, r, g]), vec![[r, g, r, g], [g, r, g, r]]);
+    assert_eq!(tile_rotations([r, r, g, g]).len(), 4);
+}
+
+#[test]
+fn test_palette_wraps_and_scrolls() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let few: Vec<Tile> = vec![[r; 4]; 4];
+    let palette = Palette::new(&few, 4.0 / 3.0);
+    assert_eq!((palette.cols, palette.rows, palette.max_scroll()), (1, 4, 0));
+    assert!((palette.tile_size - 0.15).abs() < 0.0001);
+
+    let some: Vec<Tile> = vec![[g; 4]; 20];
+    let palette = Palette::new(&some, 16.0 / 9.0);
+    assert!(palette.cols > 1);
+    assert_eq!(palette.max_scroll(), 0);
+
+    // too many to fit at a usable size, so it scrolls and keeps the selection on screen
+    let lots: Vec<Tile> = vec![[g; 4]; 200];
+    let palette = Palette::new(&lots, 4.0 / 3.0);
+    assert!(palette.tile_size >= MIN_MENU_TILE);
+    assert!(palette.max_scroll() > 0);
+    let scroll = palette.reveal(0, 199);
+    assert_eq!(scroll, palette.max_scroll());
+    assert_eq!(palette.reveal(scroll, 0), 0);
+    let gui = calculate_gui(&palette, scroll, 2, 2, 4.0 / 3.0, 199);
+    assert!(gui.iter().any(|(e, _)| *e == GUIElement::SelectionIndicator));
+    assert!(!gui.iter().any(|(e, _)| *e == GUIElement::MenuTile(0)));
+}
+
+#[test]
+fn test_palette_order() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let y = Vec3::new(1.0, 1.0, 0.0);
+    let tiles = vec![[r; 4], [g, g, g, y], [r, r, y, y], [y, g, g, g], [y, y, y, r]];
+    assert_eq!(palette_order(&tiles), vec![0, 2, 1, 3, 4]);
+}
+
+#[test]
+fn test_level_packs() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let mut lds: Vec<LevelData> = (0..4).map(|i| LevelData::new(format!("pack{}", i), 1, 1, vec![[r; 4]])).collect();
+    lds[1].pack = "hard".to_owned();
+    lds[3].pack = "hard".to_owned();
+    assert_eq!(level_packs(&lds), vec![("levels".to_owned(), vec![0, 2]), ("hard".to_owned(), vec![1, 3])]);
+
+    let gui = level_select_gui(&lds);
+    let thumbs = gui.iter().filter(|(e, _)| matches!(e, GUIElement::LevelThumb(_))).count();
+    assert_eq!(thumbs, 4);
+}
+
+#[test]
+fn test_legal_moves() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let tiles = vec![Some([r, r, r, r]), None, None];
+    let moves = legal_moves(3, 1, &tiles, [g, g, g, g], &[[g, g, g, g], [r, g, g, g]]);
+
+    assert_eq!(moves[0], None);
+    // next to the red tile only the tile with a red edge fits, once rotated
+    assert_eq!(moves[1], Some(LegalCell {selected_fits: false, palette_fits: 1}));
+    assert_eq!(moves[2], Some(LegalCell {selected_fits: true, palette_fits: 2}));
+}
+
+#[test]
+fn test_symmetry_images() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let u = Vec3::new(0.0, 0.0, 1.0);
+    let y = Vec3::new(1.0, 1.0, 0.0);
+    let t = [r, g, u, y];
+
+    assert_eq!(Symmetry::MirrorH.images(3, 2, 0, t), vec![(2, [r, y, u, g])]);
+    assert_eq!(Symmetry::MirrorV.images(3, 2, 0, t), vec![(3, [u, g, r, y])]);
+    assert_eq!(Symmetry::Rot2.images(3, 2, 0, t), vec![(5, [u, y, r, g])]);
+    // centre column maps to itself when mirrored
+    assert_eq!(Symmetry::MirrorH.images(3, 2, 1, t), vec![]);
+    // top left corner of a 3x3 goes round the other three corners
+    let rot4: Vec<usize> = Symmetry::Rot4.images(3, 3, 0, t).iter().map(|x| x.0).collect();
+    assert_eq!(rot4, vec![2, 8, 6]);
+    assert_eq!(Symmetry::Rot4.images(3, 3, 0, t)[0].1, [y, r, g, u]);
+}
+
+/*
+
+Snapshot tests: calculate_gui + draw_level for every shipped level at a couple of
+aspect ratios, compared against the triangle lists checked in under snapshots/.
+Run with UPDATE_SNAPSHOTS=1 to regenerate after an intended layout change.
+
+*/
+
+#[cfg(test)]
+const SNAPSHOT_PATH: &str = "./snapshots/";
+
+#[cfg(test)]
+fn snapshot_level(ld: &LevelData, aspect_ratio: f32) -> String {
+    use std::fmt::Write as FmtWrite;
+
+    let fallback = [Vec3::new(0.0, 0.0, 0.0); 4];
+    let selected_tile = *ld.tile_choices.first().unwrap_or(&fallback);
+    let gui = calculate_gui(&Palette::new(&ld.tile_choices, aspect_ratio), 0, ld.w, ld.h, aspect_ratio, 0);
+    let fixed: Vec<bool> = ld.fixed_tiles.iter().map(|x| x.is_some()).collect();
+
+    let mut renderer = Renderer::new();
+    let mut backend = RecordingBackend::new();
+    // cursor over the first cell so the ghost tile is covered too
+    let cursor_pos = gui.iter()
+        .find(|(e, _)| matches!(e, GUIElement::GameTile(0)))
+        .map(|(_, r)| r.centroid())
+        .unwrap_or(Vec2::new(-1.0, -1.0));
+    draw_level(&mut renderer, cursor_pos, selected_tile, &ld.tile_choices, &ld.fresh_solution(), &fixed, None, None, &gui);
+    renderer.present(&mut backend);
+
+    let mut out = String::new();
+    for t in backend.last_frame().unwrap() {
+        // every vertex's colour, so gradients across a triangle show up too
+        let verts: Vec<String> = [t.a, t.b, t.c].iter()
+            .map(|v| format!("({:.4},{:.4},{:.4}) rgba({:.4},{:.4},{:.4},{:.4})", v.pos.x, v.pos.y, v.pos.z, v.colour.x, v.colour.y, v.colour.z, v.colour.w))
+            .collect();
+        writeln!(out, "{}", verts.join(" ")).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+fn check_snapshot(name: &str, actual: &str) {
+    let path = format!("{}{}.txt", SNAPSHOT_PATH, name);
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::create_dir_all(SNAPSHOT_PATH).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1", path));
+    if expected != actual {
+        let line = expected.lines().zip(actual.lines()).position(|(a, b)| a != b)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!("snapshot {} differs at line {} (run with UPDATE_SNAPSHOTS=1 if intended)", path, line + 1);
+    }
+}
+
+#[cfg(test)]
+const SNAPSHOT_ASPECTS: [(&str, f32); 2] = [("4x3", 4.0 / 3.0), ("16x9", 16.0 / 9.0)];
+
+#[test]
+fn test_draw_level_snapshots() {
+    let mut paths: Vec<_> = std::fs::read_dir(LEVEL_PATH).unwrap()
+        .filter_map(|res| res.ok())
+        .map(|res| res.path())
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let ld = LevelData::load(contents);
+        for (aspect_name, aspect_ratio) in SNAPSHOT_ASPECTS.iter() {
+            check_snapshot(&format!("{}_{}", ld.name, aspect_name), &snapshot_level(&ld, *aspect_ratio));
+        }
+    }
+}
@@ -1,5 +1,22 @@
 pub mod renderer;
+pub mod gl_backend;
+pub mod shader;
+// test_child_aspect has an unused rect
+#[cfg_attr(test, allow(unused_variables))]
 pub mod rect;
 pub mod level;
 pub mod kmath;
-pub mod application;
\ No newline at end of file
+pub mod application;
+pub mod anim;
+pub mod audio;
+pub mod font;
+pub mod layout;
+pub mod gui;
+pub mod camera;
+pub mod viewport;
+pub mod accessibility;
+pub mod style;
+pub mod progress;
+pub mod settings;
+pub mod raster;
+pub mod svg;
\ No newline at end of file
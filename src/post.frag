@@ -0,0 +1,43 @@
+in vec2 uv;
+
+out vec4 frag_colour;
+
+uniform sampler2D scene;
+// physical pixels
+uniform vec2 resolution;
+// screen pixels per pixel, 1 is off
+uniform float pixel_size;
+uniform float fog;
+uniform vec3 fog_colour;
+uniform float vignette;
+uniform float time;
+
+float hash(vec2 p) {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+float noise(vec2 p) {
+    vec2 i = floor(p);
+    vec2 f = fract(p);
+    vec2 u = f * f * (3.0 - 2.0 * f);
+    return mix(mix(hash(i), hash(i + vec2(1.0, 0.0)), u.x),
+               mix(hash(i + vec2(0.0, 1.0)), hash(i + vec2(1.0, 1.0)), u.x), u.y);
+}
+
+void main() {
+    // snap to the middle of each big pixel
+    vec2 block = max(pixel_size, 1.0) / resolution;
+    vec2 p = (floor(uv / block) + 0.5) * block;
+    vec3 colour = texture(scene, p).rgb;
+
+    // two layers of drifting fog, thicker low down. sampled at p so it pixelates too
+    vec2 q = p * vec2(resolution.x / resolution.y, 1.0) * 4.0;
+    float n = 0.6 * noise(q + vec2(time * 0.05, 0.0)) + 0.4 * noise(q * 2.0 - vec2(0.0, time * 0.08));
+    float amount = fog * n * (0.4 + 0.6 * (1.0 - p.y));
+    colour = mix(colour, fog_colour, clamp(amount, 0.0, 1.0));
+
+    vec2 d = uv - 0.5;
+    colour *= 1.0 - vignette * dot(d, d) * 2.0;
+
+    frag_colour = vec4(colour, 1.0);
+}
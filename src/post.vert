@@ -0,0 +1,8 @@
+out vec2 uv;
+
+// one triangle over the whole screen, made from the vertex id so there's no buffer
+void main() {
+    vec2 pos = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    uv = pos;
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,109 @@
+use crate::level::*;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+/*
+
+Saved player progress, by level name: whether it's been solved and the board as
+it was left, so in-progress levels pick up where they were.
+
+*/
+
+pub const PROGRESS_PATH: &str = "./progress.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelStatus {
+    Untouched,
+    InProgress,
+    Solved,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LevelProgress {
+    pub solved: bool,
+    #[serde(default)]
+    pub solution: Vec<Option<Tile>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub levels: HashMap<String, LevelProgress>,
+}
+
+impl Progress {
+    // missing file is a fresh start, a broken one is reported and ignored
+    pub fn load(path: &str) -> Progress {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path, e);
+                Progress::default()
+            }),
+            Err(_) => Progress::default(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json_str = serde_json::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, json_str)
+    }
+
+    pub fn record(&mut self, ld: &LevelData, l: &Level) {
+        let entry = self.levels.entry(ld.name.clone()).or_default();
+        entry.solved |= l.is_solved(ld);
+        entry.solution = l.current_solution.clone();
+    }
+
+    // saved board for this level, if it still matches the level's shape and fixed tiles
+    pub fn solution(&self, ld: &LevelData) -> Option<Vec<Option<Tile>>> {
+        let saved = &self.levels.get(&ld.name)?.solution;
+        let fits = saved.len() == ld.w * ld.h && ld.fixed_tiles.iter().zip(saved)
+            .all(|(fixed, placed)| fixed.is_none() || fixed == placed);
+        if fits { Some(saved.clone()) } else { None }
+    }
+
+    pub fn status(&self, ld: &LevelData, l: &Level) -> LevelStatus {
+        let solved = self.levels.get(&ld.name).map(|p| p.solved).unwrap_or(false);
+        if solved || l.is_solved(ld) {
+            LevelStatus::Solved
+        } else if l.current_solution != ld.fixed_tiles {
+            LevelStatus::InProgress
+        } else {
+            LevelStatus::Untouched
+        }
+    }
+}
+
+#[test]
+fn test_progress_round_trip() {
+    let r = crate::kmath::Vec3::new(1.0, 0.0, 0.0);
+    let mut ld = LevelData::new("progress".to_owned(), 2, 1, vec![[r; 4]]);
+    ld.fixed_tiles[0] = Some([r; 4]);
+    let mut l = Level {
+        current_solution: ld.fresh_solution(),
+        selected_tile: [r; 4],
+        selected_tile_idx: 0,
+        history: Vec::new(),
+        symmetry: Symmetry::None,
+        unsaved: false,
+    };
+
+    let mut progress = Progress::default();
+    assert_eq!(progress.status(&ld, &l), LevelStatus::Untouched);
+    l.current_solution[1] = Some([r; 4]);
+    assert_eq!(progress.status(&ld, &l), LevelStatus::Solved);
+    progress.record(&ld, &l);
+
+    let json_str = serde_json::to_string(&progress).unwrap();
+    let loaded: Progress = serde_json::from_str(&json_str).unwrap();
+    assert_eq!(loaded.solution(&ld), Some(vec![Some([r; 4]); 2]));
+
+    // solved sticks even after clearing the board
+    l.current_solution = ld.fresh_solution();
+    assert_eq!(loaded.status(&ld, &l), LevelStatus::Solved);
+
+    // a level that's been edited since doesn't get a stale board
+    ld.fixed_tiles[1] = Some([crate::kmath::Vec3::new(0.0, 1.0, 0.0); 4]);
+    assert_eq!(loaded.solution(&ld), None);
+}
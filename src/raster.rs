@@ -0,0 +1,168 @@
+use crate::kmath::*;
+use crate::renderer::*;
+use crate::rect::*;
+use crate::level::*;
+use crate::viewport::*;
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+/*
+
+Software rasteriser for the triangles Renderer builds, so boards can be
+exported as images without a GL context.
+
+Matches what game.rs sets up in GL: 0..1 orthographic projection with y down,
+depth test where higher depth draws on top, and src alpha / 1 - src alpha blending
+over a black clear colour.
+
+*/
+
+pub struct Canvas {
+    pub w: u32,
+    pub h: u32,
+    colour: Vec<Vec4>,
+    depth: Vec<f32>,
+}
+
+impl Canvas {
+    pub fn new(w: u32, h: u32) -> Canvas {
+        Canvas {
+            w,
+            h,
+            colour: vec![Vec4::new(0.0, 0.0, 0.0, 1.0); (w*h) as usize],
+            depth: vec![0.0; (w*h) as usize],
+        }
+    }
+
+    pub fn draw_triangle(&mut self, tri: &Triangle3) {
+        let a = Vec2::new(tri.a.pos.x * self.w as f32, tri.a.pos.y * self.h as f32);
+        let b = Vec2::new(tri.b.pos.x * self.w as f32, tri.b.pos.y * self.h as f32);
+        let c = Vec2::new(tri.c.pos.x * self.w as f32, tri.c.pos.y * self.h as f32);
+
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+        let max_x = a.x.max(b.x).max(c.x).ceil().min(self.w as f32) as u32;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+        let max_y = a.y.max(b.y).max(c.y).ceil().min(self.h as f32) as u32;
+
+        for py in min_y..max_y {
+            for px in min_x..max_x {
+                // sample at pixel centres like GL does
+                let p = Vec2::new(px as f32 + 0.5, py as f32 + 0.5);
+                let wa = edge(b, c, p) / area;
+                let wb = edge(c, a, p) / area;
+                let wc = edge(a, b, p) / area;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+
+                let idx = (py * self.w + px) as usize;
+                let z = tri.a.pos.z * wa + tri.b.pos.z * wb + tri.c.pos.z * wc;
+                if z <= self.depth[idx] {
+                    continue;
+                }
+                self.depth[idx] = z;
+
+                let src = tri.a.colour * wa + tri.b.colour * wb + tri.c.colour * wc;
+                let dst = self.colour[idx];
+                self.colour[idx] = Vec4::new(
+                    src.x * src.w + dst.x * (1.0 - src.w),
+                    src.y * src.w + dst.y * (1.0 - src.w),
+                    src.z * src.w + dst.z * (1.0 - src.w),
+                    src.w * src.w + dst.w * (1.0 - src.w),
+                );
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Vec4 {
+        self.colour[(y * self.w + x) as usize]
+    }
+
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.colour.len() * 4);
+        for c in self.colour.iter() {
+            bytes.push(to_u8(c.x));
+            bytes.push(to_u8(c.y));
+            bytes.push(to_u8(c.z));
+            bytes.push(255);
+        }
+        bytes
+    }
+
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.w, self.h);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(png_err)?;
+        writer.write_image_data(&self.to_rgba8()).map_err(png_err)?;
+        Ok(())
+    }
+}
+
+impl RenderBackend for Canvas {
+    fn submit(&mut self, triangles: &[Triangle3]) {
+        for tri in triangles {
+            self.draw_triangle(tri);
+        }
+    }
+}
+
+fn edge(a: Vec2, b: Vec2, p: Vec2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+fn to_u8(x: f32) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn png_err(e: png::EncodingError) -> io::Error {
+    io::Error::other(e)
+}
+
+pub fn draw_board(renderer: &mut Renderer, ld: &LevelData, solution: &[Option<Tile>], aspect_ratio: f32) {
+    let screen_rect = Rect::new(0.0, 0.0, 1.0, 1.0);
+    renderer.draw_rect(screen_rect, Vec4::new(0.4, 0.4, 0.4, 1.0), 1.0);
+
+    let board_rect = screen_rect.child_with_aspect_ratio(ld.w as f32 / ld.h as f32 / aspect_ratio);
+    for i in 0..ld.w {
+        for j in 0..ld.h {
+            let idx = j*ld.w + i;
+            let tile_rect = board_rect.child(i as f32 / ld.w as f32, j as f32 / ld.h as f32, 1.0 / ld.w as f32, 1.0 / ld.h as f32);
+            draw_board_tile(renderer, tile_rect, solution[idx], ld.fixed_tiles[idx].is_some());
+        }
+    }
+}
+
+pub fn render_board(ld: &LevelData, solution: &[Option<Tile>], w: u32, h: u32) -> Canvas {
+    let mut renderer = Renderer::new();
+    draw_board(&mut renderer, ld, solution, Viewport::new(w, h, 1.0).aspect_ratio());
+    let mut canvas = Canvas::new(w, h);
+    renderer.present(&mut canvas);
+    canvas
+}
+
+pub fn export_board_png(ld: &LevelData, solution: &[Option<Tile>], w: u32, h: u32, path: &str) -> io::Result<()> {
+    render_board(ld, solution, w, h).save_png(path)
+}
+
+#[test]
+fn test_render_board() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let ld = LevelData::new("test".to_owned(), 2, 1, vec![[r, g, r, g]]);
+    let solution = vec![Some([r, g, r, g]), None];
+    let canvas = render_board(&ld, &solution, 200, 100);
+
+    // north triangle of the left tile is red, west is green
+    assert_eq!(canvas.pixel(50, 20), Vec4::new(1.0, 0.0, 0.0, 1.0));
+    assert_eq!(canvas.pixel(20, 50), Vec4::new(0.0, 1.0, 0.0, 1.0));
+    // right cell is empty
+    let empty = canvas.pixel(150, 50);
+    assert!((empty.x - 0.2).abs() < 0.001 && (empty.y - 0.2).abs() < 0.001);
+}
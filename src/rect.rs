@@ -1,5 +1,23 @@
 use crate::kmath::*;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Start,
+    Centre,
+    End,
+}
+
+impl Align {
+    // offset of something sized `inner` within `outer`
+    pub fn offset(&self, outer: f32, inner: f32) -> f32 {
+        match self {
+            Align::Start => 0.0,
+            Align::Centre => (outer - inner) / 2.0,
+            Align::End => outer - inner,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     pub x: f32,
@@ -51,10 +69,14 @@ impl Rect {
         }
     }
     pub fn translate(&self, v: Vec2) -> Rect {
-        return Rect::new(self.x + v.x, self.y + v.y, self.w, self.h);
+        Rect::new(self.x + v.x, self.y + v.y, self.w, self.h)
     }
     pub fn dilate(&self, d: f32) -> Rect {
-        return Rect::new(self.x - d, self.y - d, self.w + 2.0*d, self.h + 2.0*d);
+        Rect::new(self.x - d, self.y - d, self.w + 2.0*d, self.h + 2.0*d)
+    }
+    // a w by h rect placed inside this one
+    pub fn align(&self, w: f32, h: f32, h_align: Align, v_align: Align) -> Rect {
+        Rect::new(self.x + h_align.offset(self.w, w), self.y + v_align.offset(self.h, h), w, h)
     }
     pub fn left(self) -> f32 {
         self.x
@@ -81,4 +103,12 @@ fn test_child_aspect() {
     assert_eq!(Rect::new(1.0, 1.0, 1.0, 1.0).child_with_aspect_ratio(1.0), Rect::new(1.0, 1.0, 1.0, 1.0));
     assert_eq!(Rect::new(1.0, 1.0, 1.0, 1.0).child_with_aspect_ratio(2.0), Rect::new(1.0, 1.25, 1.0, 0.5));
     assert_eq!(Rect::new(1.0, 1.0, 1.0, 1.0).child_with_aspect_ratio(0.5), Rect::new(1.25,1.0, 0.5, 1.0));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_align() {
+    let r = Rect::new(1.0, 1.0, 4.0, 2.0);
+    assert_eq!(r.align(2.0, 1.0, Align::Start, Align::Start), Rect::new(1.0, 1.0, 2.0, 1.0));
+    assert_eq!(r.align(2.0, 1.0, Align::Centre, Align::Centre), Rect::new(2.0, 1.5, 2.0, 1.0));
+    assert_eq!(r.align(2.0, 1.0, Align::End, Align::End), Rect::new(3.0, 2.0, 2.0, 1.0));
+}
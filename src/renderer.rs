@@ -1,7 +1,11 @@
 use crate::kmath::*;
-use glow::*;
 use crate::rect::*;
+use crate::font;
+use crate::accessibility::*;
+use crate::style::*;
+use std::collections::HashMap;
 use std::fmt;
+use std::rc::Rc;
 
 
 /*
@@ -16,12 +20,12 @@ for minimap its like i want 0..1 to be in this spot on screen
 
 
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 #[repr(C)]
-struct Triangle3 {
-    a: Vert3,
-    b: Vert3,
-    c: Vert3,
+pub struct Triangle3 {
+    pub a: Vert3,
+    pub b: Vert3,
+    pub c: Vert3,
 }
 
 impl fmt::Debug for Triangle3 {
@@ -44,48 +48,85 @@ impl fmt::Debug for Triangle3 {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
-struct Vert3 {
-    pos: Vec3,
-    colour: Vec4,
+pub struct Vert3 {
+    pub pos: Vec3,
+    pub colour: Vec4,
+}
+
+/*
+
+Renderer only builds the triangle list, submitting it is up to a RenderBackend.
+GlBackend uploads to the GPU, RecordingBackend keeps frames in memory for tests,
+and raster::Canvas rasterises them in software.
+
+*/
+
+pub trait RenderBackend {
+    fn submit(&mut self, triangles: &[Triangle3]);
+}
+
+#[derive(Default)]
+pub struct RecordingBackend {
+    pub frames: Vec<Vec<Triangle3>>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> RecordingBackend {
+        RecordingBackend {frames: Vec::new()}
+    }
+
+    pub fn last_frame(&self) -> Option<&[Triangle3]> {
+        self.frames.last().map(|x| x.as_slice())
+    }
+}
+
+impl RenderBackend for RecordingBackend {
+    fn submit(&mut self, triangles: &[Triangle3]) {
+        self.frames.push(triangles.to_vec());
+    }
 }
 
 pub struct Renderer {
     triangles: Vec<Triangle3>,
-    vbo: NativeBuffer,
-    vao: NativeVertexArray,
 
     pub top_left: Vec2,
     pub bot_right: Vec2,
+    // screen space, anything outside is cut off
+    pub clip: Option<Rect>,
+    // see accessibility.rs, these and tile_style only matter to the draw_tile functions
+    pub colour_vision: ColourVision,
+    pub edge_symbols: bool,
+    // see style.rs
+    pub tile_style: TileStyle,
+    // stone detail by canonical seed, it only depends on the tile so it's made once
+    tile_details: HashMap<u64, Rc<TileDetail>>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Renderer {
-    pub fn new(gl: &glow::Context, aspect_ratio: f32) -> Renderer {
-        unsafe {
-            
-            // We construct a buffer and upload the data
-            let vbo = gl.create_buffer().unwrap();
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-
-            // We now construct a vertex array to describe the format of the input buffer
-            let vao = gl.create_vertex_array().unwrap();
-            gl.bind_vertex_array(Some(vao));
-            
-            gl.vertex_attrib_pointer_f32(0, 3, glow::FLOAT, false, 4*7, 0);
-            gl.enable_vertex_attrib_array(0);
-            gl.vertex_attrib_pointer_f32(1, 4, glow::FLOAT, false, 4*7, 4*3);
-            gl.enable_vertex_attrib_array(1);
-    
-            Renderer {triangles: Vec::new(),
-                vao,
-                vbo,
-                top_left: Vec2::new(0.0, 0.0),
-                bot_right: Vec2::new(1.0, 1.0),
-            }
+    pub fn new() -> Renderer {
+        Renderer {triangles: Vec::new(),
+            top_left: Vec2::new(0.0, 0.0),
+            bot_right: Vec2::new(1.0, 1.0),
+            clip: None,
+            colour_vision: ColourVision::Normal,
+            edge_symbols: false,
+            tile_style: TileStyle::Flat,
+            tile_details: HashMap::new(),
         }
     }
 
+    pub fn triangles(&self) -> &[Triangle3] {
+        &self.triangles
+    }
+
     fn push_triangle(&mut self, mut tri: Triangle3) {
         let dims = self.bot_right - self.top_left;
 
@@ -98,30 +139,113 @@ impl Renderer {
         tri.c.pos.x  = (tri.c.pos.x - self.top_left.x) / dims.x;
         tri.c.pos.y  = (tri.c.pos.y - self.top_left.y) / dims.y;
 
-        self.triangles.push(tri);
+        match self.clip {
+            Some(clip) => clip_triangle(tri, clip, &mut self.triangles),
+            None => self.triangles.push(tri),
+        }
+    }
+
+    // back to drawing straight onto the screen
+    pub fn reset_view(&mut self) {
+        self.top_left = Vec2::new(0.0, 0.0);
+        self.bot_right = Vec2::new(1.0, 1.0);
+        self.clip = None;
     }
 
     pub fn draw_rect(&mut self, r: Rect, colour: Vec4, depth: f32) {
         let v1 = Vert3 {
             pos: Vec3::new(r.x, r.y, depth),
-            colour: colour,
+            colour,
         };
         let v2 = Vert3 {
             pos: Vec3::new(r.x, r.y + r.h, depth),
-            colour: colour,
+            colour,
         };
         let v3 = Vert3 {
             pos: Vec3::new(r.x + r.w, r.y + r.h, depth),
-            colour: colour,
+            colour,
         };
         let v4 = Vert3 {
             pos: Vec3::new(r.x + r.w, r.y, depth),
-            colour: colour,
+            colour,
         };
         self.push_triangle(Triangle3{ a: v1, b: v4, c: v3 });
         self.push_triangle(Triangle3{ a: v1, b: v3, c: v2 });
     }
 
+    // edge colours after the palette and, for stone, the light on each face
+    fn face_colours(&self, colours: [Vec3; 4], angle: f32, alpha: f32) -> [Vec4; 4] {
+        let mut faces = colours.map(|c| self.colour_vision.remap(c));
+        if self.tile_style == TileStyle::Stone {
+            for (edge, c) in faces.iter_mut().enumerate() {
+                *c = shade(*c, face_shade(edge, angle));
+            }
+        }
+        faces.map(|c| c.with_w(alpha))
+    }
+
+    fn push_polygon(&mut self, points: &[Vec3], colour: Vec4) {
+        for i in 1..points.len() - 1 {
+            self.push_triangle(Triangle3 {
+                a: Vert3 {pos: points[0], colour},
+                b: Vert3 {pos: points[i], colour},
+                c: Vert3 {pos: points[i + 1], colour},
+            });
+        }
+    }
+
+    // stone detail and edge symbols on top of a tile. to_tile takes tile space (0..1,
+    // y down) to where it's drawn at the tile's depth. skipped when the tile is too
+    // small to make them out
+    fn draw_tile_overlays(&mut self, colours: [Vec3; 4], w: f32, angle: f32, alpha: f32, to_tile: impl Fn(Vec2) -> Vec3) {
+        if w / (self.bot_right.x - self.top_left.x) < 0.02 {
+            return;
+        }
+        let lift = |p: Vec2, dz: f32| to_tile(p) + Vec3::new(0.0, 0.0, dz);
+        // the palette draws its tiles with alpha over 1, which would flip these
+        let alpha = alpha.min(1.0);
+
+        if self.tile_style == TileStyle::Stone {
+            let faces = self.face_colours(colours, angle, alpha);
+            let (k, seed) = canonical(colours);
+            let detail = self.tile_details.entry(seed).or_insert_with(|| Rc::new(tile_detail(seed))).clone();
+            let place = |p: Vec2| edge_to_tile(k, p);
+            for speckle in detail.speckles.iter() {
+                let p = place(speckle.pos);
+                let face = faces[TileDetail::face_at(p)];
+                let colour = shade(Vec3::new(face.x, face.y, face.z), speckle.shade).with_w(alpha);
+                let h = speckle.size / 2.0;
+                let corners = [Vec2::new(p.x - h, p.y - h), Vec2::new(p.x + h, p.y - h), Vec2::new(p.x + h, p.y + h), Vec2::new(p.x - h, p.y + h)];
+                self.push_polygon(&corners.map(|c| lift(c, 0.01)), colour);
+            }
+            // dark line with a lit lower lip
+            for crack in detail.cracks.iter() {
+                let crack: Vec<Vec2> = crack.iter().map(|p| place(*p)).collect();
+                let n = crack.len() - 1;
+                for (i, seg) in crack.windows(2).enumerate() {
+                    let width = 0.014 * (1.0 - i as f32 / n as f32) + 0.004;
+                    let dir = (seg[1] - seg[0]).normalize();
+                    let side = Vec2::new(-dir.y, dir.x) * (width / 2.0);
+                    let quad = |off: Vec2, dz: f32| [lift(seg[0] - side + off, dz), lift(seg[1] - side + off, dz), lift(seg[1] + side + off, dz), lift(seg[0] + side + off, dz)];
+                    self.push_polygon(&quad(Vec2::new(0.0, 0.0), 0.015), Vec4::new(0.0, 0.0, 0.0, 0.45 * alpha));
+                    self.push_polygon(&quad(side * 1.5, 0.012), Vec4::new(1.0, 1.0, 1.0, 0.25 * alpha));
+                }
+            }
+        }
+
+        if self.edge_symbols {
+            for (edge, c) in colours.iter().enumerate() {
+                let colour = symbol_colour(self.colour_vision.remap(*c), alpha);
+                for poly in edge_symbol(*c) {
+                    let points: Vec<Vec3> = poly.iter().map(|p| lift(edge_to_tile(edge, *p), 0.02)).collect();
+                    self.push_polygon(&points, colour);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[allow(non_snake_case)]
     pub fn draw_tile(&mut self, r: Rect, colourN: Vec3, colourE: Vec3, colourS: Vec3, colourW: Vec3, depth: f32, alpha: f32) {
         let top_depth_colour = Vec4::new(0.7, 0.7, 0.7, 0.5);
         let bot_depth_colour = Vec4::new(0.3, 0.3, 0.3, 0.5);
@@ -133,10 +257,7 @@ impl Renderer {
         let corner4 = Vec3::new(r.x, r.y + r.h, depth);
         let center = Vec3::new(r.x + r.w/2.0, r.y + r.h/2.0, depth);
 
-        let cn = colourN.with_w(alpha);
-        let ce = colourE.with_w(alpha);
-        let cs = colourS.with_w(alpha);
-        let cw = colourW.with_w(alpha);
+        let [cn, ce, cs, cw] = self.face_colours([colourN, colourE, colourS, colourW], 0.0, alpha);
 
         self.push_triangle(Triangle3 {
             a: Vert3{pos: corner1, colour: cn},
@@ -161,12 +282,19 @@ impl Renderer {
             b: Vert3{pos: corner1, colour: cw},
             c: Vert3{pos: center, colour: cw},
         });
+        self.draw_tile_overlays([colourN, colourE, colourS, colourW], r.w, 0.0, alpha, |p| Vec3::new(r.x + p.x * r.w, r.y + p.y * r.h, depth));
+        // stone faces are lit already
+        if self.tile_style == TileStyle::Stone {
+            return;
+        }
         self.draw_rect(r.child(0.0, 0.0, 1.0, depth_thickness), top_depth_colour, depth + 0.05);
         self.draw_rect(r.child(0.0, 0.0, depth_thickness, 1.0), top_depth_colour, depth + 0.05);
         self.draw_rect(r.child(0.0, 1.0 - depth_thickness, 1.0, depth_thickness), bot_depth_colour, depth + 0.05);
         self.draw_rect(r.child(1.0 - depth_thickness, 0.0, depth_thickness, 1.0), bot_depth_colour, depth + 0.05);
     }
 
+    #[allow(clippy::too_many_arguments)]
+    #[allow(non_snake_case)]
     pub fn draw_tile_no_bevel(&mut self, r: Rect, colourN: Vec3, colourE: Vec3, colourS: Vec3, colourW: Vec3, depth: f32, alpha: f32) {
 
         let corner1 = Vec3::new(r.x, r.y, depth);
@@ -175,10 +303,51 @@ impl Renderer {
         let corner4 = Vec3::new(r.x, r.y + r.h, depth);
         let center = Vec3::new(r.x + r.w/2.0, r.y + r.h/2.0, depth);
 
-        let cn = colourN.with_w(alpha);
-        let ce = colourE.with_w(alpha);
-        let cs = colourS.with_w(alpha);
-        let cw = colourW.with_w(alpha);
+        let [cn, ce, cs, cw] = self.face_colours([colourN, colourE, colourS, colourW], 0.0, alpha);
+
+        self.push_triangle(Triangle3 {
+            a: Vert3{pos: corner1, colour: cn},
+            b: Vert3{pos: corner2, colour: cn},
+            c: Vert3{pos: center, colour: cn},
+        });
+
+        self.push_triangle(Triangle3 {
+            a: Vert3{pos: corner2, colour: ce},
+            b: Vert3{pos: corner3, colour: ce},
+            c: Vert3{pos: center, colour: ce},
+        });
+        
+        self.push_triangle(Triangle3 {
+            a: Vert3{pos: corner3, colour: cs},
+            b: Vert3{pos: corner4, colour: cs},
+            c: Vert3{pos: center, colour: cs},
+        });
+        
+        self.push_triangle(Triangle3 {
+            a: Vert3{pos: corner4, colour: cw},
+            b: Vert3{pos: corner1, colour: cw},
+            c: Vert3{pos: center, colour: cw},
+        });
+        self.draw_tile_overlays([colourN, colourE, colourS, colourW], r.w, 0.0, alpha, |p| Vec3::new(r.x + p.x * r.w, r.y + p.y * r.h, depth));
+    }
+
+    // turned about its centre, positive angle is clockwise on screen. no bevel since
+    // the bevel rects dont rotate
+    #[allow(clippy::too_many_arguments)]
+    #[allow(non_snake_case)]
+    pub fn draw_tile_rotated(&mut self, r: Rect, colourN: Vec3, colourE: Vec3, colourS: Vec3, colourW: Vec3, angle: f32, depth: f32, alpha: f32) {
+        let c = r.centroid();
+        let corner = |u: f32, v: f32| {
+            let p = Vec2::new(u, v).rotate(angle);
+            Vec3::new(c.x + p.x * r.w, c.y + p.y * r.h, depth)
+        };
+        let corner1 = corner(-0.5, -0.5);
+        let corner2 = corner(0.5, -0.5);
+        let corner3 = corner(0.5, 0.5);
+        let corner4 = corner(-0.5, 0.5);
+        let center = Vec3::new(c.x, c.y, depth);
+
+        let [cn, ce, cs, cw] = self.face_colours([colourN, colourE, colourS, colourW], angle, alpha);
 
         self.push_triangle(Triangle3 {
             a: Vert3{pos: corner1, colour: cn},
@@ -203,10 +372,12 @@ impl Renderer {
             b: Vert3{pos: corner1, colour: cw},
             c: Vert3{pos: center, colour: cw},
         });
+        self.draw_tile_overlays([colourN, colourE, colourS, colourW], r.w, angle, alpha, |p| corner(p.x - 0.5, p.y - 0.5));
     }
 
+    #[allow(clippy::too_many_arguments)]
+    #[allow(non_snake_case)]
     pub fn draw_tile_reverse_bevel(&mut self, r: Rect, colourN: Vec3, colourE: Vec3, colourS: Vec3, colourW: Vec3, depth: f32, alpha: f32) {
-        let top_depth_colour = Vec4::new(0.7, 0.7, 0.7, 0.5);
         let bot_depth_colour = Vec4::new(0.3, 0.3, 0.3, 0.5);
         let depth_thickness = 0.03;
 
@@ -216,10 +387,7 @@ impl Renderer {
         let corner4 = Vec3::new(r.x, r.y + r.h, depth);
         let center = Vec3::new(r.x + r.w/2.0, r.y + r.h/2.0, depth);
 
-        let cn = colourN.with_w(alpha);
-        let ce = colourE.with_w(alpha);
-        let cs = colourS.with_w(alpha);
-        let cw = colourW.with_w(alpha);
+        let [cn, ce, cs, cw] = self.face_colours([colourN, colourE, colourS, colourW], 0.0, alpha);
 
         self.push_triangle(Triangle3 {
             a: Vert3{pos: corner1, colour: cn},
@@ -244,6 +412,11 @@ impl Renderer {
             b: Vert3{pos: corner1, colour: cw},
             c: Vert3{pos: center, colour: cw},
         });
+        self.draw_tile_overlays([colourN, colourE, colourS, colourW], r.w, 0.0, alpha, |p| Vec3::new(r.x + p.x * r.w, r.y + p.y * r.h, depth));
+        // stone faces are lit already
+        if self.tile_style == TileStyle::Stone {
+            return;
+        }
 
         self.draw_rect(r.child(0.0, 0.0, 1.0, depth_thickness), bot_depth_colour, depth + 0.05);
         self.draw_rect(r.child(0.0, 0.0, depth_thickness, 1.0), bot_depth_colour, depth + 0.05);
@@ -251,28 +424,186 @@ impl Renderer {
         self.draw_rect(r.child(1.0 - depth_thickness, 0.0, depth_thickness, 1.0), bot_depth_colour, depth + 0.05);
     }
 
+    // size of a line of text with glyphs char_h tall. screen space is 0..1 both ways
+    // so the aspect ratio keeps the font pixels square
+    pub fn text_size(text: &str, char_h: f32, aspect_ratio: f32) -> Vec2 {
+        let px = char_h / font::GLYPH_H as f32;
+        Vec2::new(font::text_width_px(text) as f32 * px / aspect_ratio, char_h)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text(&mut self, text: &str, x: f32, y: f32, char_h: f32, aspect_ratio: f32, colour: Vec4, depth: f32) {
+        let px_h = char_h / font::GLYPH_H as f32;
+        let px_w = px_h / aspect_ratio;
+        for (i, c) in text.chars().enumerate() {
+            let glyph_x = x + (i * font::ADVANCE) as f32 * px_w;
+            for (j, row) in font::glyph(c).iter().enumerate() {
+                for (start, len) in font::row_runs(*row) {
+                    let r = Rect::new(glyph_x + start as f32 * px_w, y + j as f32 * px_h, len as f32 * px_w, px_h);
+                    self.draw_rect(r, colour, depth);
+                }
+            }
+        }
+    }
+
+    // as big as fits in r, vertically centred
+    pub fn draw_text_in(&mut self, r: Rect, text: &str, align: Align, aspect_ratio: f32, colour: Vec4, depth: f32) {
+        let full = Renderer::text_size(text, r.h, aspect_ratio);
+        let char_h = if full.x > r.w { r.h * r.w / full.x } else { r.h };
+        let size = Renderer::text_size(text, char_h, aspect_ratio);
+        let at = r.align(size.x, size.y, align, Align::Centre);
+        self.draw_text(text, at.x, at.y, char_h, aspect_ratio, colour, depth);
+    }
+
     pub fn clear(&mut self) {
         self.triangles.clear();
     }
 
-    pub fn present(&mut self, gl: &glow::Context) {
-        unsafe {
-            let gpu_bytes: &[u8] = core::slice::from_raw_parts(
-                self.triangles.as_ptr() as *const u8,
-                3 * 4 * 7 * self.triangles.len(),
-            ); // 3 for points in triangle, 4 for bytes in float, 6 for floats in vertex
-            gl.bind_vertex_array(Some(self.vao));
-            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
-            gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, gpu_bytes, glow::DYNAMIC_DRAW);
-            gl.draw_arrays(glow::TRIANGLES, 0, self.triangles.len() as i32 * 3);
-            //gl.draw_arrays(glow::TRIANGLES, 0, 6);
+    pub fn present(&mut self, backend: &mut dyn RenderBackend) {
+        backend.submit(&self.triangles);
+    }
+}
+
+// cut a triangle down to the part inside clip, fanning out whatever polygon is left
+fn clip_triangle(tri: Triangle3, clip: Rect, out: &mut Vec<Triangle3>) {
+    let verts = [tri.a, tri.b, tri.c];
+    // a hair of slack so tiles lying exactly on the edge aren't split up
+    let eps = 0.00001;
+    let inside = |v: &Vert3| v.pos.x >= clip.x - eps && v.pos.x <= clip.right() + eps && v.pos.y >= clip.y - eps && v.pos.y <= clip.bot() + eps;
+    if verts.iter().all(inside) {
+        out.push(tri);
+        return;
+    }
+
+    // signed distance inside each edge, sutherland hodgman one edge at a time
+    let edges: [&dyn Fn(&Vert3) -> f32; 4] = [
+        &|v| v.pos.x - clip.x,
+        &|v| clip.right() - v.pos.x,
+        &|v| v.pos.y - clip.y,
+        &|v| clip.bot() - v.pos.y,
+    ];
+    let mut poly = verts.to_vec();
+    for dist in edges.iter() {
+        let mut next = Vec::new();
+        for i in 0..poly.len() {
+            let (p, q) = (poly[i], poly[(i + 1) % poly.len()]);
+            let (dp, dq) = (dist(&p), dist(&q));
+            if dp >= 0.0 {
+                next.push(p);
+            }
+            if (dp >= 0.0) != (dq >= 0.0) {
+                let t = dp / (dp - dq);
+                next.push(Vert3 {pos: p.pos.lerp(q.pos, t), colour: p.colour.lerp(q.colour, t)});
+            }
+        }
+        poly = next;
+        if poly.len() < 3 {
+            return;
         }
     }
+    for i in 1..poly.len() - 1 {
+        out.push(Triangle3 {a: poly[0], b: poly[i], c: poly[i + 1]});
+    }
+}
+
+#[test]
+fn test_recording_backend() {
+    let mut renderer = Renderer::new();
+    let mut backend = RecordingBackend::new();
+
+    renderer.top_left = Vec2::new(0.5, 0.0);
+    renderer.bot_right = Vec2::new(1.0, 0.5);
+    renderer.draw_rect(Rect::new(0.5, 0.0, 0.25, 0.25), Vec4::new(1.0, 0.0, 0.0, 1.0), 2.0);
+    renderer.present(&mut backend);
+    renderer.clear();
+    renderer.present(&mut backend);
+
+    assert_eq!(backend.frames.len(), 2);
+    assert!(backend.last_frame().unwrap().is_empty());
+    let first = &backend.frames[0];
+    assert_eq!(first.len(), 2);
+    // view transform is applied before submission
+    assert_eq!(first[0].a.pos, Vec3::new(0.0, 0.0, 2.0));
+    assert_eq!(first[0].c.pos, Vec3::new(0.5, 0.5, 2.0));
+}
+
+#[test]
+fn test_clip() {
+    let mut renderer = Renderer::new();
+    renderer.clip = Some(Rect::new(0.0, 0.0, 0.5, 0.5));
+    renderer.draw_rect(Rect::new(0.25, 0.25, 0.5, 0.5), Vec4::new(1.0, 0.0, 0.0, 1.0), 2.0);
+    renderer.draw_rect(Rect::new(0.6, 0.6, 0.1, 0.1), Vec4::new(1.0, 0.0, 0.0, 1.0), 2.0);
+
+    let area: f32 = renderer.triangles().iter().map(|t| {
+        let (ab, ac) = (t.b.pos - t.a.pos, t.c.pos - t.a.pos);
+        (ab.x * ac.y - ab.y * ac.x).abs() / 2.0
+    }).sum();
+    assert!((area - 0.0625).abs() < 0.0001);
+    for tri in renderer.triangles() {
+        for v in [tri.a, tri.b, tri.c] {
+            assert!(v.pos.x <= 0.5 && v.pos.y <= 0.5);
+        }
+    }
+}
+
+#[test]
+fn test_edge_symbols() {
+    let r = Rect::new(0.1, 0.1, 0.2, 0.2);
+    let (red, black) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+    let mut renderer = Renderer::new();
+    renderer.draw_tile_no_bevel(r, red, black, black, black, 1.0, 1.0);
+    assert_eq!(renderer.triangles().len(), 4);
+
+    // the red edge gets remapped and a dot on top, black stays plain
+    renderer.clear();
+    renderer.colour_vision = ColourVision::Deuteranopia;
+    renderer.edge_symbols = true;
+    renderer.draw_tile_no_bevel(r, red, black, black, black, 1.0, 1.0);
+    let tris = renderer.triangles();
+    assert_eq!(tris.len(), 6);
+    assert_eq!(tris[0].a.colour, ColourVision::Deuteranopia.remap(red).with_w(1.0));
+    assert_eq!(tris[3].a.colour, black.with_w(1.0));
+    assert!(tris[4..].iter().all(|t| t.a.pos.z > 1.0 && t.a.pos.y < 0.2));
+
+    // too small to make out
+    renderer.clear();
+    renderer.draw_tile_no_bevel(Rect::new(0.0, 0.0, 0.01, 0.01), red, black, black, black, 1.0, 1.0);
+    assert_eq!(renderer.triangles().len(), 4);
+}
 
-    pub fn destroy(&self, gl: &glow::Context) {
-        unsafe {
-            gl.delete_buffer(self.vbo);
-            gl.delete_vertex_array(self.vao);
+#[test]
+fn test_stone_tiles() {
+    let r = Rect::new(0.1, 0.1, 0.2, 0.2);
+    let (red, black) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
+    let mut renderer = Renderer::new();
+    renderer.tile_style = TileStyle::Stone;
+
+    // detail sits just above the faces and the bevel rects are left off
+    renderer.draw_tile(r, red, black, black, black, 1.0, 1.0);
+    assert!(renderer.triangles().len() > 4);
+    assert!(renderer.triangles()[4..].iter().all(|t| t.a.pos.z > 1.0 && t.a.pos.z < 1.05));
+
+    // turned, it's the same tile and the same detail
+    renderer.draw_tile(r, black, red, black, black, 1.0, 1.0);
+    assert_eq!(renderer.tile_details.len(), 1);
+}
+
+#[test]
+fn test_draw_text() {
+    let mut renderer = Renderer::new();
+    let size = Renderer::text_size("ab", 0.07, 2.0);
+    assert!((size.x - 0.11 / 2.0).abs() < 0.0001);
+
+    renderer.draw_text("-", 0.0, 0.0, 0.07, 1.0, Vec4::new(1.0, 1.0, 1.0, 1.0), 5.0);
+    // one run for the bar of the minus
+    assert_eq!(renderer.triangles().len(), 2);
+
+    renderer.clear();
+    let r = Rect::new(0.0, 0.0, 0.1, 0.5);
+    renderer.draw_text_in(r, "wide text", Align::End, 1.0, Vec4::new(1.0, 1.0, 1.0, 1.0), 5.0);
+    for tri in renderer.triangles() {
+        for v in [tri.a, tri.b, tri.c] {
+            assert!(v.pos.x >= -0.0001 && v.pos.x <= 0.1001);
         }
     }
-}
\ No newline at end of file
+}
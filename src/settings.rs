@@ -0,0 +1,202 @@
+use crate::kmath::*;
+use crate::accessibility::*;
+use crate::style::*;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::io;
+
+/*
+
+Player settings, kept in settings.json next to the levels. Anything missing
+from the file takes its default, so older files keep working as settings are
+added. Window size is logical pixels, see viewport.rs.
+
+Vsync is fixed when the GL context is made, so changing it only takes effect
+next time the game starts. Everything else applies straight away.
+
+fog, vignette and pixel_size go to the post-process shader (src/post.frag) that
+the whole frame is drawn through. 0 fog or vignette is off, and so is a
+pixel_size of 1. The settings screen turns them on at the defaults here, any
+other strength can be set in the file.
+
+*/
+
+pub const SETTINGS_PATH: &str = "./settings.json";
+
+// logical window sizes to cycle through
+pub const RESOLUTIONS: [(u32, u32); 7] = [(1024, 768), (1200, 900), (1280, 720), (1600, 900), (1600, 1200), (1920, 1080), (2560, 1440)];
+// 0 is uncapped
+pub const FRAME_CAPS: [u32; 5] = [30, 60, 120, 144, 0];
+// screen pixels per pixel after pixelation
+pub const PIXEL_SIZES: [u32; 4] = [1, 2, 3, 4];
+pub const DEFAULT_FOG: f32 = 0.35;
+pub const DEFAULT_VIGNETTE: f32 = 0.4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    Borderless,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Theme {
+    Grey,
+    Dark,
+    Light,
+}
+
+// colours for everything around the board
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColours {
+    pub background: Vec4,
+    pub button: Vec4,
+    pub text: Vec4,
+    pub message: Vec4,
+}
+
+impl Theme {
+    pub fn next(self) -> Theme {
+        match self {
+            Theme::Grey => Theme::Dark,
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Grey,
+        }
+    }
+
+    pub fn colours(self) -> ThemeColours {
+        match self {
+            Theme::Grey => ThemeColours {
+                background: Vec4::new(0.4, 0.4, 0.4, 1.0),
+                button: Vec4::new(0.25, 0.25, 0.25, 1.0),
+                text: Vec4::new(1.0, 1.0, 1.0, 1.0),
+                message: Vec4::new(1.0, 1.0, 0.8, 1.0),
+            },
+            Theme::Dark => ThemeColours {
+                background: Vec4::new(0.1, 0.1, 0.12, 1.0),
+                button: Vec4::new(0.22, 0.22, 0.25, 1.0),
+                text: Vec4::new(0.9, 0.9, 0.9, 1.0),
+                message: Vec4::new(0.9, 0.8, 0.5, 1.0),
+            },
+            Theme::Light => ThemeColours {
+                background: Vec4::new(0.85, 0.85, 0.82, 1.0),
+                button: Vec4::new(0.3, 0.3, 0.32, 1.0),
+                text: Vec4::new(0.1, 0.1, 0.1, 1.0),
+                message: Vec4::new(0.3, 0.2, 0.0, 1.0),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub window_mode: WindowMode,
+    pub width: u32,
+    pub height: u32,
+    pub vsync: bool,
+    pub frame_cap: u32,
+    pub volume: f32,
+    pub muted: bool,
+    pub theme: Theme,
+    pub colour_vision: ColourVision,
+    pub edge_symbols: bool,
+    pub tile_style: TileStyle,
+    pub fog: f32,
+    pub vignette: f32,
+    pub pixel_size: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_mode: WindowMode::Windowed,
+            width: 1200,
+            height: 900,
+            vsync: true,
+            frame_cap: 60,
+            volume: 0.8,
+            muted: false,
+            theme: Theme::Grey,
+            colour_vision: ColourVision::Normal,
+            edge_symbols: false,
+            tile_style: TileStyle::Flat,
+            fog: 0.0,
+            vignette: 0.0,
+            pixel_size: 1,
+        }
+    }
+}
+
+impl Settings {
+    // missing file is the defaults, a broken one is reported and ignored
+    pub fn load(path: &str) -> Settings {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("{}: {}", path, e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json_str = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json_str)
+    }
+
+    pub fn next_resolution(&mut self) {
+        let i = RESOLUTIONS.iter().position(|r| *r == (self.width, self.height));
+        let (w, h) = RESOLUTIONS[i.map(|i| (i + 1) % RESOLUTIONS.len()).unwrap_or(0)];
+        self.width = w;
+        self.height = h;
+    }
+
+    pub fn next_frame_cap(&mut self) {
+        let i = FRAME_CAPS.iter().position(|x| *x == self.frame_cap);
+        self.frame_cap = FRAME_CAPS[i.map(|i| (i + 1) % FRAME_CAPS.len()).unwrap_or(0)];
+    }
+
+    pub fn next_pixel_size(&mut self) {
+        let i = PIXEL_SIZES.iter().position(|x| *x == self.pixel_size);
+        self.pixel_size = PIXEL_SIZES[i.map(|i| (i + 1) % PIXEL_SIZES.len()).unwrap_or(0)];
+    }
+
+    // nothing for the post-process pass to do, the frame can go straight to the window
+    pub fn post_process_off(&self) -> bool {
+        self.fog <= 0.0 && self.vignette <= 0.0 && self.pixel_size <= 1
+    }
+
+    // seconds a frame should take at least, None when uncapped
+    pub fn frame_time(&self) -> Option<f64> {
+        if self.frame_cap == 0 { None } else { Some(1.0 / self.frame_cap as f64) }
+    }
+}
+
+#[test]
+fn test_settings() {
+    // older files missing fields get the defaults for them
+    let settings: Settings = serde_json::from_str(r#"{"vsync": false, "theme": "Dark"}"#).unwrap();
+    assert!(!settings.vsync);
+    assert_eq!(settings.theme, Theme::Dark);
+    assert_eq!(settings.frame_cap, 60);
+
+    let mut settings = Settings {width: 123, ..Settings::default()};
+    settings.next_resolution();
+    assert_eq!((settings.width, settings.height), RESOLUTIONS[0]);
+    settings.next_resolution();
+    assert_eq!((settings.width, settings.height), RESOLUTIONS[1]);
+
+    settings.frame_cap = 144;
+    settings.next_frame_cap();
+    assert_eq!(settings.frame_time(), None);
+    settings.next_frame_cap();
+    assert_eq!(settings.frame_cap, 30);
+
+    assert!(settings.post_process_off());
+    settings.next_pixel_size();
+    assert_eq!(settings.pixel_size, 2);
+    assert!(!settings.post_process_off());
+
+    let json_str = serde_json::to_string(&settings).unwrap();
+    assert_eq!(serde_json::from_str::<Settings>(&json_str).unwrap(), settings);
+}
@@ -0,0 +1,236 @@
+use glow::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+/*
+
+Named GL programs, built from a vertex and fragment shader file each. The
+sources are read from disk rather than built in, so they can be edited with the
+game running: poll looks at the files' modified times every POLL_INTERVAL and
+rebuilds any program whose files changed. If the new version doesn't compile or
+link the error is printed and the old program carries on, so a typo doesn't take
+the game down.
+
+Uniform locations are looked up once per program and kept, a reload clears them
+since the new program can lay them out differently.
+
+The game has two programs: SCENE draws Renderer's triangles, which is the tiles
+and the UI in one batch, and POST is the post-process pass (see PostTarget).
+
+*/
+
+pub const SCENE: &str = "scene";
+pub const POST: &str = "post";
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const SHADER_VERSION: &str = "#version 410";
+
+// the files a program is built from, and when they were last seen to change
+pub struct ShaderFiles {
+    pub vert: PathBuf,
+    pub frag: PathBuf,
+    mtimes: [Option<SystemTime>; 2],
+}
+
+impl ShaderFiles {
+    pub fn new(vert: &Path, frag: &Path) -> ShaderFiles {
+        let mut files = ShaderFiles {vert: vert.to_owned(), frag: frag.to_owned(), mtimes: [None, None]};
+        files.mtimes = files.read_mtimes();
+        files
+    }
+
+    fn read_mtimes(&self) -> [Option<SystemTime>; 2] {
+        [&self.vert, &self.frag].map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    }
+
+    // true once for each change to either file
+    pub fn changed(&mut self) -> bool {
+        let mtimes = self.read_mtimes();
+        if mtimes == self.mtimes {
+            return false;
+        }
+        self.mtimes = mtimes;
+        true
+    }
+
+    pub fn read(&self) -> Result<(String, String), String> {
+        let read = |path: &Path| fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e));
+        Ok((read(&self.vert)?, read(&self.frag)?))
+    }
+}
+
+// on failure everything made so far is deleted and the error has the info log
+pub fn compile_program(gl: &glow::Context, files: &ShaderFiles) -> Result<NativeProgram, String> {
+    let (vert_source, frag_source) = files.read()?;
+    unsafe {
+        let program = gl.create_program()?;
+        let sources = [
+            (glow::VERTEX_SHADER, &files.vert, vert_source),
+            (glow::FRAGMENT_SHADER, &files.frag, frag_source),
+        ];
+        let mut shaders = Vec::with_capacity(sources.len());
+        let mut result = Ok(());
+        for (shader_type, path, source) in sources.iter() {
+            let shader = gl.create_shader(*shader_type)?;
+            gl.shader_source(shader, &format!("{}\n{}", SHADER_VERSION, source));
+            gl.compile_shader(shader);
+            gl.attach_shader(program, shader);
+            shaders.push(shader);
+            if !gl.get_shader_compile_status(shader) {
+                result = Err(format!("{}: {}", path.display(), gl.get_shader_info_log(shader)));
+                break;
+            }
+        }
+        if result.is_ok() {
+            gl.link_program(program);
+            if !gl.get_program_link_status(program) {
+                result = Err(format!("{} + {}: {}", files.vert.display(), files.frag.display(), gl.get_program_info_log(program)));
+            }
+        }
+        for shader in shaders {
+            gl.detach_shader(program, shader);
+            gl.delete_shader(shader);
+        }
+        match result {
+            Ok(()) => Ok(program),
+            Err(e) => {
+                gl.delete_program(program);
+                Err(e)
+            },
+        }
+    }
+}
+
+struct Program {
+    program: NativeProgram,
+    files: ShaderFiles,
+    uniforms: HashMap<String, Option<NativeUniformLocation>>,
+}
+
+pub struct Shaders {
+    gl: Rc<glow::Context>,
+    programs: HashMap<String, Program>,
+    last_poll: Instant,
+}
+
+impl Shaders {
+    pub fn new(gl: Rc<glow::Context>) -> Shaders {
+        Shaders {gl, programs: HashMap::new(), last_poll: Instant::now()}
+    }
+
+    // replaces any program already under name
+    pub fn add(&mut self, name: &str, vert: &str, frag: &str) -> Result<(), String> {
+        let files = ShaderFiles::new(Path::new(vert), Path::new(frag));
+        let program = compile_program(&self.gl, &files)?;
+        let old = self.programs.insert(name.to_owned(), Program {program, files, uniforms: HashMap::new()});
+        if let Some(old) = old {
+            unsafe { self.gl.delete_program(old.program) };
+        }
+        Ok(())
+    }
+
+    pub fn program(&self, name: &str) -> Option<NativeProgram> {
+        self.programs.get(name).map(|p| p.program)
+    }
+
+    pub fn use_program(&self, name: &str) {
+        unsafe { self.gl.use_program(self.program(name)) };
+    }
+
+    // None for unknown programs, and for uniforms the shader doesn't use since GL drops those
+    pub fn uniform(&mut self, name: &str, uniform: &str) -> Option<NativeUniformLocation> {
+        let gl = &self.gl;
+        let p = self.programs.get_mut(name)?;
+        if let Some(location) = p.uniforms.get(uniform) {
+            return *location;
+        }
+        let location = unsafe { gl.get_uniform_location(p.program, uniform) };
+        p.uniforms.insert(uniform.to_owned(), location);
+        location
+    }
+
+    // these set uniforms on the program in use, which should be name
+    pub fn set_i32(&mut self, name: &str, uniform: &str, x: i32) {
+        let location = self.uniform(name, uniform);
+        unsafe { self.gl.uniform_1_i32(location.as_ref(), x) };
+    }
+
+    pub fn set_f32(&mut self, name: &str, uniform: &str, x: f32) {
+        let location = self.uniform(name, uniform);
+        unsafe { self.gl.uniform_1_f32(location.as_ref(), x) };
+    }
+
+    pub fn set_vec2(&mut self, name: &str, uniform: &str, x: f32, y: f32) {
+        let location = self.uniform(name, uniform);
+        unsafe { self.gl.uniform_2_f32(location.as_ref(), x, y) };
+    }
+
+    pub fn set_vec3(&mut self, name: &str, uniform: &str, x: f32, y: f32, z: f32) {
+        let location = self.uniform(name, uniform);
+        unsafe { self.gl.uniform_3_f32(location.as_ref(), x, y, z) };
+    }
+
+    pub fn set_mat4(&mut self, name: &str, uniform: &str, m: &[f32; 16]) {
+        let location = self.uniform(name, uniform);
+        unsafe { self.gl.uniform_matrix_4_f32_slice(location.as_ref(), false, m) };
+    }
+
+    // call every frame, only looks at the files every POLL_INTERVAL
+    pub fn poll(&mut self) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        for (name, p) in self.programs.iter_mut() {
+            if !p.files.changed() {
+                continue;
+            }
+            match compile_program(&self.gl, &p.files) {
+                Ok(program) => {
+                    unsafe { self.gl.delete_program(p.program) };
+                    p.program = program;
+                    p.uniforms.clear();
+                    eprintln!("reloaded {} shader", name);
+                },
+                Err(e) => eprintln!("couldn't reload {} shader, keeping the old one: {}", name, e),
+            }
+        }
+    }
+
+    pub fn destroy(&mut self) {
+        for (_, p) in self.programs.drain() {
+            unsafe { self.gl.delete_program(p.program) };
+        }
+    }
+}
+
+#[test]
+fn test_shader_files() {
+    // per process so runs at the same time don't trip over each other
+    let dir = std::env::temp_dir();
+    let name = format!("wang_test_shader_{}", std::process::id());
+    let (vert, frag) = (dir.join(format!("{}.vert", name)), dir.join(format!("{}.frag", name)));
+    fs::write(&vert, "void main() {}").unwrap();
+    fs::write(&frag, "void main() {}").unwrap();
+
+    let mut files = ShaderFiles::new(&vert, &frag);
+    assert!(!files.changed());
+    assert_eq!(files.read().unwrap().0, "void main() {}");
+
+    // an edit is picked up once
+    let later = SystemTime::now() + Duration::from_secs(10);
+    fs::File::options().write(true).open(&frag).unwrap().set_modified(later).unwrap();
+    assert!(files.changed());
+    assert!(!files.changed());
+
+    // as is the file going away, and reading it says which file
+    fs::remove_file(&vert).unwrap();
+    assert!(files.changed());
+    assert!(files.read().unwrap_err().contains(&format!("{}.vert", name)));
+    fs::remove_file(&frag).unwrap();
+}
@@ -0,0 +1,155 @@
+use crate::kmath::*;
+use crate::accessibility::*;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+
+/*
+
+How tiles look past their edge colours. Flat is the plain four triangles, Stone
+treats a tile as a shallow pyramid of rock:
+
+Lighting: each face slopes up from its edge to the centre, which gives it a
+normal, lit from the top left like the bevel rects in Renderer::draw_tile. The
+normal turns with a rotated tile so the light stays put.
+
+Detail: speckles of lighter and darker stone and a crack or two, all from an rng
+seeded by the tile's colours. The seed and the pattern are taken from the
+tile's smallest rotation, so a tile keeps its cracks when it's turned or moved
+and the pattern turns with it. Two identical tiles look the same, which is fine,
+they are the same tile.
+
+Detail is made in the smallest rotation's tile space (0..1, y down), so it only
+depends on the seed and Renderer can keep one per distinct tile. Renderer turns
+it onto the real tile with edge_to_tile(k, p) and maps it on.
+
+*/
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileStyle {
+    Flat,
+    Stone,
+}
+
+impl TileStyle {
+    pub fn next(self) -> TileStyle {
+        match self {
+            TileStyle::Flat => TileStyle::Stone,
+            TileStyle::Stone => TileStyle::Flat,
+        }
+    }
+}
+
+// how far a face rises per unit in towards the centre
+const SLOPE: f32 = 0.5;
+const AMBIENT: f32 = 0.4;
+const DIFFUSE: f32 = 0.6;
+// towards the light, z out of the screen
+const LIGHT: Vec3 = Vec3 {x: -0.379, y: -0.531, z: 0.758};
+
+// brightness of face edge (N, E, S, W) of a tile turned by angle, 1 is a flat face
+pub fn face_shade(edge: usize, angle: f32) -> f32 {
+    let out = edge_to_tile(edge, Vec2::new(0.5, 0.0)) - Vec2::new(0.5, 0.5);
+    let out = (out * 2.0).rotate(angle);
+    let normal = Vec3::new(out.x * SLOPE, out.y * SLOPE, 1.0).normalize();
+    let n_dot_l = normal.x * LIGHT.x + normal.y * LIGHT.y + normal.z * LIGHT.z;
+    (AMBIENT + DIFFUSE * n_dot_l.max(0.0)) / (AMBIENT + DIFFUSE * LIGHT.z)
+}
+
+// brighter goes towards white so saturated colours still light up
+pub fn shade(c: Vec3, amount: f32) -> Vec3 {
+    if amount > 1.0 {
+        c.lerp(Vec3::new(1.0, 1.0, 1.0), amount - 1.0)
+    } else {
+        c * amount
+    }
+}
+
+pub struct Speckle {
+    pub pos: Vec2,
+    pub size: f32,
+    pub shade: f32,
+}
+
+pub struct TileDetail {
+    pub speckles: Vec<Speckle>,
+    // polylines, thinning towards the end
+    pub cracks: Vec<Vec<Vec2>>,
+}
+
+impl TileDetail {
+    // which face, ie edge N, E, S, W, a tile space point is on
+    pub fn face_at(p: Vec2) -> usize {
+        let (dx, dy) = (p.x - 0.5, p.y - 0.5);
+        if dy.abs() >= dx.abs() {
+            if dy < 0.0 { 0 } else { 2 }
+        } else if dx > 0.0 { 1 } else { 3 }
+    }
+}
+
+// quarter turns that bring the tile to its smallest rotation, and a seed for that rotation
+pub fn canonical(colours: [Vec3; 4]) -> (usize, u64) {
+    let key = |k: usize| -> Vec<u32> {
+        (0..4).flat_map(|i| {
+            let c = colours[(i + k) % 4];
+            [c.x.to_bits(), c.y.to_bits(), c.z.to_bits()]
+        }).collect()
+    };
+    let k = (0..4).min_by_key(|k| key(*k)).unwrap();
+    // fnv-1a
+    let seed = key(k).iter().fold(0xcbf29ce484222325u64, |h, x| (h ^ *x as u64).wrapping_mul(0x100000001b3));
+    (k, seed)
+}
+
+// detail for the smallest rotation of the tile with this seed, see canonical
+pub fn tile_detail(seed: u64) -> TileDetail {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let speckles = (0..14).map(|_| Speckle {
+        pos: Vec2::new(rng.gen_range(0.08..0.92), rng.gen_range(0.08..0.92)),
+        size: rng.gen_range(0.02..0.045),
+        shade: if rng.gen_bool(0.5) { rng.gen_range(0.75..0.9) } else { rng.gen_range(1.08..1.2) },
+    }).collect();
+
+    // in from an edge, wandering towards the middle
+    let cracks = (0..rng.gen_range(1..=2)).map(|_| {
+        let edge = rng.gen_range(0..4);
+        let mut p = Vec2::new(rng.gen_range(0.25..0.75), 0.0);
+        let mut heading: f32 = rng.gen_range(-0.6..0.6);
+        let mut points = vec![edge_to_tile(edge, p)];
+        for _ in 0..rng.gen_range(3..=5) {
+            heading += rng.gen_range(-0.7..0.7);
+            let len = rng.gen_range(0.06..0.11);
+            p = p + Vec2::new(heading.sin() * len, heading.cos().abs() * len);
+            p = Vec2::new(p.x.clamp(0.05, 0.95), p.y.min(0.9));
+            points.push(edge_to_tile(edge, p));
+        }
+        points
+    }).collect();
+
+    TileDetail {speckles, cracks}
+}
+
+#[test]
+fn test_style() {
+    // top left faces catch the light
+    assert!(face_shade(0, 0.0) > 1.0 && face_shade(3, 0.0) > 1.0);
+    assert!(face_shade(1, 0.0) < 1.0 && face_shade(2, 0.0) < 1.0);
+    // half a turn swaps them over
+    assert!((face_shade(0, std::f32::consts::PI) - face_shade(2, 0.0)).abs() < 0.001);
+
+    let (r, g, b) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(shade(r, 1.2).y > 0.0 && shade(r, 0.5) == Vec3::new(0.5, 0.0, 0.0));
+
+    // the same tile turned a quarter has the same detail, turned one more quarter onto it
+    let (ka, seed) = canonical([r, g, b, b]);
+    let (kt, turned_seed) = canonical([b, r, g, b]);
+    assert_eq!(seed, turned_seed);
+    assert_eq!(kt, (ka + 1) % 4);
+    let a = tile_detail(seed);
+    assert!(!a.cracks.is_empty());
+    // cracks stay on the tile
+    assert!(a.cracks.iter().flatten().all(|p| p.x >= 0.0 && p.x <= 1.0 && p.y >= 0.0 && p.y <= 1.0));
+    assert_eq!(TileDetail::face_at(Vec2::new(0.5, 0.1)), 0);
+    assert_eq!(TileDetail::face_at(Vec2::new(0.1, 0.5)), 3);
+}
@@ -0,0 +1,110 @@
+use crate::kmath::*;
+use crate::rect::*;
+use crate::level::*;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::{self, Write};
+
+/*
+
+Vector export of boards and palettes. Each tile is its four coloured triangles,
+same geometry as Renderer::draw_tile_no_bevel, so sheets can be printed or pulled
+into other tools.
+
+*/
+
+pub struct SvgWriter {
+    w: f32,
+    h: f32,
+    body: String,
+}
+
+impl SvgWriter {
+    pub fn new(w: f32, h: f32) -> SvgWriter {
+        SvgWriter {w, h, body: String::new()}
+    }
+
+    pub fn rect(&mut self, r: Rect, colour: Vec3) {
+        writeln!(self.body, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            r.x, r.y, r.w, r.h, svg_colour(colour)).unwrap();
+    }
+
+    pub fn tile(&mut self, r: Rect, tile: Tile) {
+        let corner1 = Vec2::new(r.x, r.y);
+        let corner2 = Vec2::new(r.x + r.w, r.y);
+        let corner3 = Vec2::new(r.x + r.w, r.y + r.h);
+        let corner4 = Vec2::new(r.x, r.y + r.h);
+        let center = Vec2::new(r.x + r.w/2.0, r.y + r.h/2.0);
+
+        writeln!(self.body, "<g>").unwrap();
+        self.triangle(corner1, corner2, center, tile[0]);
+        self.triangle(corner2, corner3, center, tile[1]);
+        self.triangle(corner3, corner4, center, tile[2]);
+        self.triangle(corner4, corner1, center, tile[3]);
+        writeln!(self.body, "</g>").unwrap();
+    }
+
+    fn triangle(&mut self, a: Vec2, b: Vec2, c: Vec2, colour: Vec3) {
+        writeln!(self.body, "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>",
+            a.x, a.y, b.x, b.y, c.x, c.y, svg_colour(colour)).unwrap();
+    }
+
+    pub fn finish(&self) -> String {
+        format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n{}</svg>\n",
+            self.w, self.h, self.w, self.h, self.body)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.finish().as_bytes())
+    }
+}
+
+fn svg_colour(c: Vec3) -> String {
+    let to_u8 = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", to_u8(c.x), to_u8(c.y), to_u8(c.z))
+}
+
+pub fn board_svg(ld: &LevelData, solution: &[Option<Tile>], tile_size: f32) -> SvgWriter {
+    let empty_colour = Vec3::new(0.2, 0.2, 0.2);
+    let mut svg = SvgWriter::new(ld.w as f32 * tile_size, ld.h as f32 * tile_size);
+
+    for j in 0..ld.h {
+        for i in 0..ld.w {
+            let r = Rect::new(i as f32 * tile_size, j as f32 * tile_size, tile_size, tile_size);
+            match solution[j*ld.w + i] {
+                Some(tile) => svg.tile(r, tile),
+                None => svg.rect(r, empty_colour),
+            }
+        }
+    }
+    svg
+}
+
+pub fn palette_svg(tiles: &[Tile], columns: usize, tile_size: f32) -> SvgWriter {
+    let columns = columns.max(1);
+    let rows = tiles.len().div_ceil(columns);
+    let gap = tile_size * 0.25;
+    let stride = tile_size + gap;
+    let mut svg = SvgWriter::new(columns as f32 * stride + gap, rows as f32 * stride + gap);
+
+    for (i, tile) in tiles.iter().enumerate() {
+        let r = Rect::new(gap + (i % columns) as f32 * stride, gap + (i / columns) as f32 * stride, tile_size, tile_size);
+        svg.tile(r, *tile);
+    }
+    svg
+}
+
+#[test]
+fn test_board_svg() {
+    let r = Vec3::new(1.0, 0.0, 0.0);
+    let g = Vec3::new(0.0, 1.0, 0.0);
+    let ld = LevelData::new("test".to_owned(), 2, 1, vec![[r, g, r, g]]);
+    let out = board_svg(&ld, &[Some([r, g, r, g]), None], 10.0).finish();
+
+    assert!(out.contains("viewBox=\"0 0 20 10\""));
+    assert_eq!(out.matches("<polygon").count(), 4);
+    assert!(out.contains("<polygon points=\"0,0 10,0 5,5\" fill=\"#ff0000\"/>"));
+    assert!(out.contains("<polygon points=\"0,10 0,0 5,5\" fill=\"#00ff00\"/>"));
+    assert!(out.contains("<rect x=\"10\" y=\"0\" width=\"10\" height=\"10\" fill=\"#333333\"/>"));
+}
@@ -0,0 +1,182 @@
+/*
+play levels in a terminal, eg over ssh or with no GL
+
+arrows / hjkl   move cursor
+1-9, tab        select palette tile
+q, e            rotate selected tile
+r               toggle auto rotate on placement
+space, enter    place
+x, backspace    remove
+u               undo
+n, m            previous / next level
+p, s            toggle edit mode, save level
+esc             quit
+
+each tile is drawn as a 3x3 block of coloured cells: north along the top row,
+south along the bottom, west and east either side of the middle
+*/
+
+use wang::application::*;
+use wang::kmath::*;
+use wang::level::*;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+use std::time::Instant;
+
+const CELL_W: u16 = 2;
+const TILE_W: u16 = CELL_W * 3;
+const TILE_H: u16 = 3;
+
+// restores the terminal even if we panic
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(out: &mut Stdout) -> io::Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn term_colour(c: Vec3) -> Color {
+    let to_u8 = |x: f32| (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::Rgb {r: to_u8(c.x), g: to_u8(c.y), b: to_u8(c.z)}
+}
+
+fn draw_cell(out: &mut Stdout, x: u16, y: u16, colour: Vec3, text: &str) -> io::Result<()> {
+    queue!(out, MoveTo(x, y), SetBackgroundColor(term_colour(colour)), Print(text))
+}
+
+fn draw_tile(out: &mut Stdout, x: u16, y: u16, tile: Option<Tile>, centre: &str) -> io::Result<()> {
+    let empty = Vec3::new(0.2, 0.2, 0.2);
+    let middle = Vec3::new(0.1, 0.1, 0.1);
+    let [n, e, s, w] = tile.unwrap_or([empty; 4]);
+
+    for i in 0..3 {
+        draw_cell(out, x + i * CELL_W, y, n, "  ")?;
+        draw_cell(out, x + i * CELL_W, y + 2, s, "  ")?;
+    }
+    draw_cell(out, x, y + 1, w, "  ")?;
+    draw_cell(out, x + CELL_W, y + 1, middle, centre)?;
+    draw_cell(out, x + 2 * CELL_W, y + 1, e, "  ")?;
+    Ok(())
+}
+
+fn draw(out: &mut Stdout, application: &Application, cursor: usize) -> io::Result<()> {
+    let ld = application.level_data();
+    let l = application.level();
+
+    queue!(out, ResetColor, Clear(ClearType::All), MoveTo(0, 0))?;
+    let (left, right) = application.status_text();
+    let mut status = format!("Wang's Garden  {}  {}", left, right);
+    if let Some(msg) = application.message() {
+        status.push_str("  | ");
+        status.push_str(msg);
+    }
+    queue!(out, Print(status))?;
+
+    let board_x = 2;
+    let board_y = 2;
+    for j in 0..ld.h {
+        for i in 0..ld.w {
+            let idx = j * ld.w + i;
+            let centre = match (idx == cursor, ld.fixed_tiles[idx].is_some()) {
+                (true, _) => "[]",
+                (false, true) => "##",
+                (false, false) => "  ",
+            };
+            draw_tile(out, board_x + i as u16 * TILE_W, board_y + j as u16 * TILE_H, l.current_solution[idx], centre)?;
+        }
+    }
+
+    let palette_x = board_x + ld.w as u16 * TILE_W + 4;
+    queue!(out, ResetColor, SetForegroundColor(Color::White), MoveTo(palette_x, board_y), Print("selected"))?;
+    draw_tile(out, palette_x + 2, board_y + 1, Some(l.selected_tile), "  ")?;
+
+    let palette_y = board_y + TILE_H + 2;
+    // listed in the order tab walks, numbered for the digit keys
+    for (slot, &i) in palette_order(&ld.tile_choices).iter().enumerate() {
+        let y = palette_y + slot as u16 * (TILE_H + 1);
+        let marker = if i as i32 == l.selected_tile_idx { ">" } else { " " };
+        queue!(out, ResetColor, MoveTo(palette_x, y + 1), Print(format!("{}{}", marker, slot + 1)))?;
+        draw_tile(out, palette_x + 3, y, Some(ld.tile_choices[i]), "  ")?;
+    }
+
+    let help_y = (board_y + ld.h as u16 * TILE_H + 1).max(palette_y + ld.tile_choices.len() as u16 * (TILE_H + 1));
+    queue!(out, ResetColor, MoveTo(0, help_y),
+        Print("arrows move  1-9/tab select  q/e rotate  r auto  space place  x remove  u undo  n/m level  p edit  s save  esc quit"))?;
+    out.flush()
+}
+
+fn main() -> io::Result<()> {
+    let mut application = Application::new();
+    let mut cursor = 0;
+
+    let mut out = io::stdout();
+    let _guard = TerminalGuard::new(&mut out)?;
+
+    let mut last_event = Instant::now();
+    loop {
+        draw(&mut out, &application, cursor)?;
+
+        let event = event::read()?;
+        // only redrawn on input, so messages expire by the time between keys
+        application.update(last_event.elapsed().as_secs_f32());
+        last_event = Instant::now();
+        let key = match event {
+            Event::Key(KeyEvent {code, modifiers}) => (code, modifiers),
+            _ => continue,
+        };
+
+        let (w, h) = (application.level_data().w, application.level_data().h);
+        let (x, y) = (cursor % w, cursor / w);
+        match key {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => break,
+            (KeyCode::Left, _) | (KeyCode::Char('h'), _) if x > 0 => cursor -= 1,
+            (KeyCode::Right, _) | (KeyCode::Char('l'), _) if x < w - 1 => cursor += 1,
+            (KeyCode::Up, _) | (KeyCode::Char('k'), _) if y > 0 => cursor -= w,
+            (KeyCode::Down, _) | (KeyCode::Char('j'), _) if y < h - 1 => cursor += w,
+            (KeyCode::Char(c), _) if c.is_ascii_digit() && c != '0' => {
+                let slot = c.to_digit(10).unwrap() as usize - 1;
+                if let Some(&idx) = palette_order(&application.level_data().tile_choices).get(slot) {
+                    application.apply(Action::SelectTile(idx));
+                }
+            },
+            (KeyCode::Tab, _) => application.apply(Action::NextTile),
+            (KeyCode::BackTab, _) => application.apply(Action::PrevTile),
+            (KeyCode::Char(' '), _) | (KeyCode::Enter, _) => application.apply(Action::Place(cursor)),
+            (KeyCode::Char('x'), _) | (KeyCode::Backspace, _) | (KeyCode::Delete, _) => application.apply(Action::Remove(cursor)),
+            (KeyCode::Char('q'), _) => application.apply(Action::RotateCcw),
+            (KeyCode::Char('e'), _) => application.apply(Action::RotateCw),
+            (KeyCode::Char('r'), _) => application.apply(Action::ToggleAutoRotate),
+            (KeyCode::Char('n'), _) => application.apply(Action::PrevLevel),
+            (KeyCode::Char('m'), _) => application.apply(Action::NextLevel),
+            (KeyCode::Char('p'), _) => application.apply(Action::ToggleEdit),
+            (KeyCode::Char('s'), _) => application.apply(Action::Save),
+            (KeyCode::Char('u'), _) => application.apply(Action::Undo),
+            _ => {},
+        }
+
+        // level may have changed size
+        let ld = application.level_data();
+        if cursor >= ld.w * ld.h {
+            cursor = 0;
+        }
+    }
+
+    // saves progress
+    application.apply(Action::Quit);
+    Ok(())
+}
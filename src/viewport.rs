@@ -0,0 +1,62 @@
+use crate::kmath::*;
+
+/*
+
+The window as the game sees it. glutin gives sizes and cursor positions in
+physical pixels, which on a HiDPI display are scale_factor times the logical
+size the window was asked for. Everything past here is in 0..1 screen space
+with y down (the orthographic projection maps that onto the whole window), so
+the shape of the window only shows up as the aspect ratio.
+
+Drawing and picking both go through the same Viewport, so a click lands on what
+was drawn under it whatever the size or scale of the window.
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    // physical pixels
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+impl Viewport {
+    pub fn new(width: u32, height: u32, scale_factor: f64) -> Viewport {
+        Viewport {width, height, scale_factor}
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    // minimised windows report a size of 0, nothing to draw then
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.is_empty() { 1.0 } else { self.width as f32 / self.height as f32 }
+    }
+
+    // physical pixel position, eg from CursorMoved, to screen space
+    pub fn to_screen(&self, x: f64, y: f64) -> Vec2 {
+        if self.is_empty() {
+            return Vec2::new(-1.0, -1.0);
+        }
+        Vec2::new((x / self.width as f64) as f32, (y / self.height as f64) as f32)
+    }
+}
+
+#[test]
+fn test_viewport() {
+    // a 800x600 logical window on a 2x display
+    let mut viewport = Viewport::new(1600, 1200, 2.0);
+    assert_eq!(viewport.aspect_ratio(), 4.0 / 3.0);
+    assert_eq!(viewport.to_screen(800.0, 300.0), Vec2::new(0.5, 0.25));
+
+    viewport.resize(0, 0);
+    assert_eq!(viewport.aspect_ratio(), 1.0);
+    assert_eq!(viewport.to_screen(10.0, 10.0), Vec2::new(-1.0, -1.0));
+}